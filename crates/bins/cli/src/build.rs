@@ -1,9 +1,17 @@
-use crate::index::{Index, IndexEntry};
-use anyhow::Result;
+use crate::index::{index, IndexEntry, TreeOrder};
+use crate::search::{search_index, search_path};
+use crate::tables::{tables_path, TableExport};
+use crate::template::{fill, Templates};
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use neu_config::Config;
 use neu_db::Diagnostician;
+use neu_eval::Value;
 use neu_render::db::Renderer;
+use neu_render::escape;
 use neu_syntax::ast::ArticleItem;
 use neu_syntax::db::{ArticleId, FileId, FileKind, Kind, Parser};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -21,6 +29,13 @@ impl From<anyhow::Error> for IoError {
 
 #[salsa::query_group(BuilderDatabase)]
 pub trait Builder: salsa::Database + Renderer + Parser + Diagnostician {
+    #[salsa::input]
+    fn config(&self) -> Arc<Config>;
+    #[salsa::input]
+    fn templates(&self) -> Arc<Templates>;
+    #[salsa::input]
+    fn tree_order(&self) -> TreeOrder;
+
     fn build_all(&self, root: PathBuf, dist: PathBuf) -> Result<(), IoError>;
 
     fn build_article(
@@ -30,25 +45,139 @@ pub trait Builder: salsa::Database + Renderer + Parser + Diagnostician {
         path: FileId,
         article_item: ArticleItem,
         articles_path: PathBuf,
-    ) -> Result<IndexEntry, IoError>;
+    ) -> Result<(IndexEntry, Vec<PathBuf>), IoError>;
+
+    fn build_page(
+        &self,
+        entry: IndexEntry,
+        path: FileId,
+        article_item: ArticleItem,
+        navigation: Navigation,
+        site_path: PathBuf,
+    ) -> Result<PathBuf, IoError>;
+}
+
+// Everything `build` writes into dist, the directory itself also marks the workspace root.
+const GENERATED: &[&str] = &[
+    "articles",
+    "site",
+    "index.json",
+    "search.json",
+    "diagnostics.json",
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Navigation {
+    prev: Option<IndexEntry>,
+    next: Option<IndexEntry>,
+}
+
+pub fn configure(db: &mut dyn Builder, config: Config) -> Arc<Config> {
+    db.set_audience(config.audience);
+    db.set_lints(config.lints.clone());
+    let config = Arc::new(config);
+    db.set_config(config.clone());
+    config
 }
 
 pub fn build(db: &mut dyn Builder, root: &Path, dist: &Path) -> Result<()> {
     scan_all(db, root)?;
     db.build_all(root.into(), dist.into())?;
 
+    let denied = db.denied_diagnostics();
+    if !denied.is_empty() {
+        return Err(anyhow!(
+            "Build failed, {} diagnostics come from denied lints",
+            denied.len()
+        ));
+    }
+
     println!("Build finished");
 
     Ok(())
 }
 
-pub(crate) fn scan_all(db: &mut dyn Builder, root: &Path) -> Result<()> {
-    let articles = glob::glob(&format!("{}/**/*.md", root.display()))?
+pub fn clean(root: &Path, dist: &Path) -> Result<()> {
+    for generated in GENERATED {
+        let path = root.join(dist).join(generated);
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        } else if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+    }
+
+    println!("Clean finished");
+
+    Ok(())
+}
+
+// Removes files in `dir` the build didn't produce and directories left empty.
+fn remove_stale(dir: &Path, outputs: &HashSet<PathBuf>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_stale(&path, outputs)?;
+            if std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path)?;
+            }
+        } else if !outputs.contains(&path) {
+            log::info!("Removing {}", path.display());
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn file_kind(path: &Path) -> Option<FileKind> {
+    match path.extension()?.to_str()? {
+        "md" => Some(FileKind::Md),
+        "neu" => Some(FileKind::Neu),
+        _ => None,
+    }
+}
+
+fn scan(root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let files = glob::glob(&format!("{}/{}", root.display(), pattern))?
         .map(|entry| entry.map_err(anyhow::Error::from))
         .collect::<Result<Vec<_>>>();
-    let articles = articles?;
+    Ok(files?.into_iter().filter(|path| path.is_file()).collect())
+}
+
+pub(crate) fn scan_templates(db: &mut dyn Builder, root: &Path) -> Result<()> {
+    let templates = Templates::load(&root.join(&db.config().templates))?;
+    db.set_templates(Arc::new(templates));
+    Ok(())
+}
 
-    db.set_all_neu(Arc::default());
+pub(crate) fn scan_all(db: &mut dyn Builder, root: &Path) -> Result<()> {
+    scan_templates(db, root)?;
+
+    let config = db.config();
+    let mut articles = BTreeSet::new();
+    let mut modules = BTreeSet::new();
+    for pattern in &config.sources {
+        for path in scan(root, pattern)? {
+            if !config.is_source(root, &path) {
+                continue;
+            }
+            match file_kind(&path) {
+                Some(FileKind::Md) if !config.is_template(root, &path) => articles.insert(path),
+                Some(FileKind::Neu) => modules.insert(path),
+                _ => false,
+            };
+        }
+    }
+
+    db.set_all_neu(Arc::new(
+        modules
+            .iter()
+            .map(|path| db.file_id((path.display().to_string(), FileKind::Neu)))
+            .collect(),
+    ));
     db.set_all_mds(Arc::new(
         articles
             .iter()
@@ -56,8 +185,12 @@ pub(crate) fn scan_all(db: &mut dyn Builder, root: &Path) -> Result<()> {
             .collect(),
     ));
 
-    for entry in &articles {
-        let file_id = db.file_id((entry.display().to_string(), FileKind::Md));
+    for (entry, kind) in articles
+        .iter()
+        .map(|entry| (entry, FileKind::Md))
+        .chain(modules.iter().map(|entry| (entry, FileKind::Neu)))
+    {
+        let file_id = db.file_id((entry.display().to_string(), kind));
 
         let file = std::fs::read_to_string(entry)?;
         let input = &file;
@@ -77,12 +210,31 @@ fn build_all_inner(db: &dyn Builder, root: &Path, dist: &Path) -> Result<()> {
 
     let parsed_articles = db.parse_all_mds();
 
-    let index = parsed_articles
+    // Secret articles are left out of player builds altogether.
+    let mut outputs = HashSet::new();
+    let articles = parsed_articles
         .into_iter()
-        .map(|(kind, id, path, ast)| db.build_article(kind, id, path, ast, articles_path.clone()))
-        .collect::<Result<Vec<_>, IoError>>();
+        .filter(|(kind, id, _, _)| !db.is_hidden(kind.clone(), id.clone()))
+        .map(|(kind, id, path, ast)| {
+            let (entry, files) =
+                db.build_article(kind, id, path, ast.clone(), articles_path.clone())?;
+            outputs.extend(files);
+            Ok((entry, path, ast))
+        })
+        .collect::<Result<Vec<_>, IoError>>()?;
+
+    let site_path = root.join(dist).join("site");
+    outputs.extend(build_site(db, &articles, &site_path)?);
+
+    // Removed, renamed and now secret articles leave their old files behind otherwise.
+    remove_stale(&articles_path, &outputs)?;
+    remove_stale(&site_path, &outputs)?;
 
-    let index: Index = index?.into();
+    let search_index = search_index(db);
+    let mut file = std::fs::File::create(search_path(root, dist))?;
+    file.write_all(serde_json::to_vec(&search_index)?.as_slice())?;
+
+    let index = index(db, root, &articles_path);
     let index_path = root.join(dist).join("index.json");
     let mut file = std::fs::File::create(index_path)?;
     file.write_all(serde_json::to_vec(&index)?.as_slice())?;
@@ -130,9 +282,9 @@ fn build_article(
     path: FileId,
     article_item: ArticleItem,
     articles_path: PathBuf,
-) -> Result<IndexEntry, IoError> {
-    let entry = build_article_inner(db, kind, id, path, article_item, &articles_path)?;
-    Ok(entry)
+) -> Result<(IndexEntry, Vec<PathBuf>), IoError> {
+    let built = build_article_inner(db, kind, id, path, article_item, &articles_path)?;
+    Ok(built)
 }
 
 fn build_article_inner(
@@ -142,40 +294,212 @@ fn build_article_inner(
     path: FileId,
     article_item: ArticleItem,
     articles_path: &Path,
-) -> Result<IndexEntry> {
+) -> Result<(IndexEntry, Vec<PathBuf>)> {
     //log::info!("Building {}:{}, {:?}, {:?}. {:?}", kind, id, path, article_item, articles_path);
-    let strukt = article_item
-        .strukt
-        .map(|strukt| db.eval(path, strukt))
-        .and_then(|strukt_eval| strukt_eval.value.clone())
-        .and_then(|value| value.into_struct());
+    let strukt = fields(db, path, &article_item);
 
-    let title = strukt
-        .as_ref()
-        .and_then(|value| value.get("title"))
-        .map(ToString::to_string)
-        .unwrap_or_else(|| "???".to_string());
-
-    let title = title.trim_matches('"');
-    log::info!("Building {}:{} - {}", kind, id, title);
-    log::debug!("Title - {}", title);
+    let entry = index_entry(db, kind, id, path, &article_item, articles_path);
+    log::info!("Building {}:{} - {}", entry.kind, entry.id, entry.title);
+    log::debug!("Title - {}", entry.title);
 
-    // TODO: instead of rerendering it make a link to the original article.
-    let rendered = db.render_md(path);
+    // Nested items get just their own part of the file.
+    let rendered = db.render_ast(path, article_item);
 
-    let kind_path = articles_path.join(&kind);
-    let item_path = kind_path.join(&format!("{}.html", id));
+    let kind_path = articles_path.join(&entry.kind);
+    let item_path = PathBuf::from(&entry.path);
     log::debug!("To {}", item_path.display());
     std::fs::create_dir_all(&kind_path)?;
     let mut file = std::fs::File::create(&item_path)?;
     file.write_all(rendered.output.as_bytes())?;
+    let mut outputs = vec![item_path.clone()];
+
+    let tables = strukt
+        .iter()
+        .flatten()
+        .filter_map(|(field, value)| match value {
+            Value::Table(table) => Some((field, TableExport::new(table, db.html_policy()))),
+            _ => None,
+        })
+        .collect::<BTreeMap<_, _>>();
+    if !tables.is_empty() {
+        let tables_path = tables_path(articles_path, &entry.kind, &entry.id);
+        let mut file = std::fs::File::create(&tables_path)?;
+        file.write_all(serde_json::to_vec(&tables)?.as_slice())?;
+        outputs.push(tables_path);
+    }
+
+    Ok((entry, outputs))
+}
 
-    Ok(IndexEntry {
+fn fields(
+    db: &dyn Builder,
+    path: FileId,
+    article_item: &ArticleItem,
+) -> Option<IndexMap<String, Value>> {
+    article_item
+        .strukt
+        .map(|strukt| db.eval(path, strukt))
+        .and_then(|strukt_eval| strukt_eval.value.clone())
+        .and_then(|value| value.into_struct())
+        .map(|fields| neu_render::visible_fields(fields, db.audience()))
+}
+
+pub(crate) fn index_entry(
+    db: &dyn Builder,
+    kind: Kind,
+    id: ArticleId,
+    path: FileId,
+    article_item: &ArticleItem,
+    articles_path: &Path,
+) -> IndexEntry {
+    let title = fields(db, path, article_item)
+        .as_ref()
+        .and_then(|value| value.get("title"))
+        .map(neu_render::plain_text)
+        .unwrap_or_else(|| "???".to_string());
+    let item_path = articles_path.join(&kind).join(&format!("{}.html", id));
+
+    IndexEntry {
         kind,
         id,
-        title: title.into(),
+        title,
         path: item_path.display().to_string(),
-    })
+    }
+}
+
+fn build_site(
+    db: &dyn Builder,
+    articles: &[(IndexEntry, FileId, ArticleItem)],
+    site_path: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut outputs = vec![];
+    let mut kinds: BTreeMap<&str, Vec<&(IndexEntry, FileId, ArticleItem)>> = BTreeMap::new();
+    for article in articles {
+        kinds.entry(&article.0.kind).or_default().push(article);
+    }
+
+    let mut index_body = String::new();
+    for (kind, articles) in kinds.iter_mut() {
+        articles.sort_by(|(a, _, _), (b, _, _)| (&a.title, &a.id).cmp(&(&b.title, &b.id)));
+
+        index_body.push_str(&format!(
+            r#"<h2 id="{kind}">{kind}</h2><ul>"#,
+            kind = escape(kind)
+        ));
+        for (idx, (entry, path, article_item)) in articles.iter().enumerate() {
+            index_body.push_str(&format!("<li>{}</li>", page_link(entry, None)));
+
+            let navigation = Navigation {
+                prev: idx
+                    .checked_sub(1)
+                    .and_then(|prev| articles.get(prev))
+                    .map(|(entry, _, _)| entry.clone()),
+                next: articles.get(idx + 1).map(|(entry, _, _)| entry.clone()),
+            };
+            outputs.push(db.build_page(
+                entry.clone(),
+                *path,
+                article_item.clone(),
+                navigation,
+                site_path.into(),
+            )?);
+        }
+        index_body.push_str("</ul>");
+    }
+
+    let mut slots = BTreeMap::new();
+    slots.insert("title", "Index".to_string());
+    slots.insert("title_text", "Index".to_string());
+    slots.insert("body", index_body);
+
+    std::fs::create_dir_all(site_path)?;
+    let index_path = site_path.join("index.html");
+    let mut file = std::fs::File::create(&index_path)?;
+    file.write_all(fill(db.templates().index(), &slots).as_bytes())?;
+    outputs.push(index_path);
+
+    Ok(outputs)
+}
+
+fn page_link(entry: &IndexEntry, class: Option<&str>) -> String {
+    let class = class
+        .map(|class| format!(r#" class="{}""#, class))
+        .unwrap_or_default();
+    format!(
+        r#"<a{} href="/{}/{}/">{}</a>"#,
+        class,
+        escape(&entry.kind),
+        escape(&entry.id),
+        escape(&entry.title)
+    )
+}
+
+fn build_page(
+    db: &dyn Builder,
+    entry: IndexEntry,
+    path: FileId,
+    article_item: ArticleItem,
+    navigation: Navigation,
+    site_path: PathBuf,
+) -> Result<PathBuf, IoError> {
+    let page_path = build_page_inner(db, &entry, path, article_item, &navigation, &site_path)?;
+    Ok(page_path)
+}
+
+fn build_page_inner(
+    db: &dyn Builder,
+    entry: &IndexEntry,
+    path: FileId,
+    article_item: ArticleItem,
+    navigation: &Navigation,
+    site_path: &Path,
+) -> Result<PathBuf> {
+    let rendered = db.render_ast(path, article_item);
+    let parts = &rendered.parts;
+    let title_text = escape(&entry.title);
+
+    let breadcrumbs = format!(
+        r#"<nav class="breadcrumbs"><a href="/">Index</a> / <a href="/#{kind}">{kind}</a> / {title}</nav>"#,
+        kind = escape(&entry.kind),
+        title = title_text
+    );
+    let mut nav = String::new();
+    if let Some(prev) = &navigation.prev {
+        nav.push_str(&page_link(prev, Some("prev")));
+    }
+    if let Some(next) = &navigation.next {
+        nav.push_str(&page_link(next, Some("next")));
+    }
+
+    let mut slots = BTreeMap::new();
+    slots.insert("kind", escape(&entry.kind));
+    slots.insert("id", escape(&entry.id));
+    slots.insert(
+        "title",
+        parts.title.clone().unwrap_or_else(|| title_text.clone()),
+    );
+    slots.insert("title_text", title_text);
+    slots.insert("side_table", parts.side_table.clone());
+    slots.insert("toc", parts.toc.clone());
+    slots.insert("mentions", parts.mentions.clone());
+    slots.insert("body", parts.body.clone());
+    slots.insert("breadcrumbs", breadcrumbs);
+    slots.insert(
+        "navigation",
+        format!(r#"<nav class="navigation">{}</nav>"#, nav),
+    );
+
+    let templates = db.templates();
+    let page = fill(templates.article(&entry.kind), &slots);
+
+    let page_path = site_path.join(&entry.kind).join(&entry.id);
+    log::debug!("To {}", page_path.display());
+    std::fs::create_dir_all(&page_path)?;
+    let page_path = page_path.join("index.html");
+    let mut file = std::fs::File::create(&page_path)?;
+    file.write_all(page.as_bytes())?;
+
+    Ok(page_path)
 }
 
 #[cfg(test)]
@@ -206,6 +530,11 @@ mod tests {
         md_b.write_file(&md_file_b)?;
 
         let mut db = Database::default();
+        db.set_html_policy(Default::default());
+        db.set_audience(Default::default());
+        db.set_tree_order(Default::default());
+        db.set_lints(Default::default());
+        db.set_config(Default::default());
         build(&mut db, &root, &dist)?;
 
         let res_a = temp
@@ -221,6 +550,9 @@ mod tests {
 
         res_a.assert(predicate::path::exists());
         res_b.assert(predicate::path::exists());
+        temp.child(".neu")
+            .child("search.json")
+            .assert(predicate::path::exists());
 
         temp.close()?;
         Ok(())
@@ -247,6 +579,11 @@ mod tests {
         md_b.write_file(&md_file_b)?;
 
         let mut db = Database::default();
+        db.set_html_policy(Default::default());
+        db.set_audience(Default::default());
+        db.set_tree_order(Default::default());
+        db.set_lints(Default::default());
+        db.set_config(Default::default());
         build(&mut db, &root, &dist)?;
 
         let res_a = temp
@@ -279,6 +616,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn site_pages() -> Result<()> {
+        let md_file_a: PathBuf = "tests/a.md".into();
+        let md_file_b: PathBuf = "tests/b.md".into();
+
+        let temp = assert_fs::TempDir::new()?;
+        let root = temp.path();
+        let dist = PathBuf::from(".neu");
+
+        temp.child("a.md").write_file(&md_file_a)?;
+        temp.child("b.md").write_file(&md_file_b)?;
+
+        let mut db = Database::default();
+        db.set_html_policy(Default::default());
+        db.set_audience(Default::default());
+        db.set_tree_order(Default::default());
+        db.set_lints(Default::default());
+        db.set_config(Default::default());
+        build(&mut db, &root, &dist)?;
+
+        let site = temp.child(".neu").child("site");
+        site.child("index.html")
+            .assert(predicate::str::contains(r#"<a href="/test/1234aaaa/">"#));
+        site.child("test")
+            .child("1234aaaa")
+            .child("index.html")
+            .assert(predicate::str::starts_with("<!DOCTYPE html>"))
+            .assert(predicate::str::contains(r#"<nav class="breadcrumbs">"#));
+
+        let templates = temp.child("templates");
+        templates.create_dir_all()?;
+        templates
+            .child("test.html")
+            .write_str("<main>{{ title }}</main>")?;
+        build(&mut db, &root, &dist)?;
+
+        site.child("test")
+            .child("1234aaaa")
+            .child("index.html")
+            .assert(predicate::str::starts_with("<main>"));
+
+        temp.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn stale_outputs_and_clean() -> Result<()> {
+        let md_file_a: PathBuf = "tests/a.md".into();
+        let md_file_b: PathBuf = "tests/b.md".into();
+
+        let temp = assert_fs::TempDir::new()?;
+        let root = temp.path();
+        let dist = PathBuf::from(".neu");
+
+        temp.child("a.md").write_file(&md_file_a)?;
+        let md_b = temp.child("b.md");
+        md_b.write_file(&md_file_b)?;
+
+        let mut db = Database::default();
+        db.set_html_policy(Default::default());
+        db.set_audience(Default::default());
+        db.set_tree_order(Default::default());
+        db.set_lints(Default::default());
+        db.set_config(Default::default());
+        build(&mut db, &root, &dist)?;
+
+        let neu = temp.child(".neu");
+        let res_b = neu.child("articles").child("test").child("1234bbbb.html");
+        let page_b = neu.child("site").child("test").child("1234bbbb");
+        res_b.assert(predicate::path::exists());
+        page_b.assert(predicate::path::exists());
+
+        std::fs::remove_file(md_b.path())?;
+        build(&mut db, &root, &dist)?;
+
+        res_b.assert(predicate::path::missing());
+        page_b.assert(predicate::path::missing());
+        neu.child("articles")
+            .child("test")
+            .child("1234aaaa.html")
+            .assert(predicate::path::exists());
+
+        clean(&root, &dist)?;
+        neu.assert(predicate::path::exists());
+        neu.child("articles").assert(predicate::path::missing());
+        neu.child("site").assert(predicate::path::missing());
+        neu.child("index.json").assert(predicate::path::missing());
+
+        temp.close()?;
+        Ok(())
+    }
+
     fn modified(child: &ChildPath) -> Result<SystemTime> {
         let metadata = std::fs::metadata(child.path())?;
         let time = metadata.modified()?;
@@ -0,0 +1,42 @@
+use crate::build::{scan_all, Builder};
+use anyhow::{anyhow, Result};
+use neu_render::GraphFormat;
+use std::path::Path;
+
+pub fn graph(
+    db: &mut dyn Builder,
+    root: &Path,
+    format: GraphFormat,
+    kinds: &[String],
+    article: Option<&str>,
+    depth: Option<usize>,
+    output: Option<&Path>,
+) -> Result<()> {
+    scan_all(db, root)?;
+
+    let mut graph = (*db.article_graph()).clone();
+    for node in graph.nodes.iter_mut() {
+        if let Ok(file) = Path::new(&node.file).strip_prefix(root) {
+            node.file = file.display().to_string();
+        }
+    }
+
+    let article = match article {
+        Some(article) => {
+            let mut parts = article.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(kind), Some(id)) if graph.contains(kind, id) => Some((kind, id)),
+                _ => return Err(anyhow!("Couldn't find article {}", article)),
+            }
+        }
+        None => None,
+    };
+
+    let exported = graph.filter(kinds, article, depth).export(format);
+    match output {
+        Some(output) => std::fs::write(output, exported)?,
+        None => print!("{}", exported),
+    }
+
+    Ok(())
+}
@@ -1,11 +1,47 @@
+use crate::build::{index_entry, Builder};
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(Debug, Serialize)]
-#[allow(dead_code)] // TODO: Finish me
-enum Tree {
+// Entry, its source file relative to the workspace and the article it is nested in.
+type Article = (IndexEntry, PathBuf, Option<(String, String)>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeOrder {
+    Title,
+    Path,
+    Kind,
+}
+
+impl Default for TreeOrder {
+    fn default() -> Self {
+        Self::Title
+    }
+}
+
+impl FromStr for TreeOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "title" => Ok(Self::Title),
+            "path" => Ok(Self::Path),
+            "kind" => Ok(Self::Kind),
+            _ => Err(format!(
+                "Unknown order `{}`, expected `title`, `path` or `kind`",
+                s
+            )),
+        }
+    }
+}
+
+// Directories first, sorted by name, then articles with their nested items.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub enum Tree {
     Dir(String, Vec<Tree>),
-    File(usize),
+    File(usize, Vec<Tree>),
     None,
 }
 
@@ -17,21 +53,24 @@ pub struct Index {
     project: Tree,
 }
 
-impl From<Vec<IndexEntry>> for Index {
-    fn from(vec: Vec<IndexEntry>) -> Self {
+impl Index {
+    pub fn new(articles: Vec<Article>, order: TreeOrder) -> Self {
         let mut kind: BTreeMap<String, Vec<usize>> = BTreeMap::default();
         let mut abc: BTreeMap<char, Vec<usize>> = BTreeMap::default();
-        let project: Tree = Tree::None;
+        let project = project_tree(&articles, order);
 
-        vec.iter().enumerate().for_each(|(idx, entry)| {
-            kind.entry(entry.kind.clone()).or_default().push(idx);
+        articles
+            .iter()
+            .enumerate()
+            .for_each(|(idx, (entry, _, _))| {
+                kind.entry(entry.kind.clone()).or_default().push(idx);
 
-            abc.entry(entry.title.chars().next().unwrap_or(' '))
-                .or_default()
-                .push(idx);
-        });
+                abc.entry(entry.title.chars().next().unwrap_or(' '))
+                    .or_default()
+                    .push(idx);
+            });
 
-        let data = vec;
+        let data = articles.into_iter().map(|(entry, _, _)| entry).collect();
         Self {
             data,
             abc,
@@ -41,10 +80,219 @@ impl From<Vec<IndexEntry>> for Index {
     }
 }
 
-#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+// Secret articles are left out of player builds, like their pages.
+pub(crate) fn index(db: &dyn Builder, root: &Path, articles_path: &Path) -> Index {
+    let parents = db.article_parents();
+    let articles = db
+        .parse_all_mds()
+        .into_iter()
+        .filter(|(kind, id, _, _)| !db.is_hidden(kind.clone(), id.clone()))
+        .map(|(kind, id, path, article_item)| {
+            let entry = index_entry(db, kind, id, path, &article_item, articles_path);
+            let source = PathBuf::from(db.lookup_file_id(path).0);
+            let file = source.strip_prefix(root).unwrap_or(&source).to_path_buf();
+            let parent = parents
+                .get(&(entry.kind.clone(), entry.id.clone()))
+                .cloned();
+            (entry, file, parent)
+        })
+        .collect();
+    Index::new(articles, db.tree_order())
+}
+
+#[derive(Default)]
+struct Dir {
+    dirs: BTreeMap<String, Dir>,
+    files: Vec<usize>,
+}
+
+fn project_tree(articles: &[Article], order: TreeOrder) -> Tree {
+    if articles.is_empty() {
+        return Tree::None;
+    }
+
+    let positions = articles
+        .iter()
+        .enumerate()
+        .map(|(idx, (entry, _, _))| ((entry.kind.as_str(), entry.id.as_str()), idx))
+        .collect::<HashMap<_, _>>();
+
+    let mut root = Dir::default();
+    let mut nested: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (idx, (_, file, parent)) in articles.iter().enumerate() {
+        // Items whose parent isn't in the index (e.g. a secret one) stay in their directory.
+        let parent = parent
+            .as_ref()
+            .and_then(|(kind, id)| positions.get(&(kind.as_str(), id.as_str())));
+        match parent {
+            Some(parent) if *parent != idx => nested.entry(*parent).or_default().push(idx),
+            _ => {
+                let mut dir = &mut root;
+                for component in file.parent().into_iter().flat_map(|path| path.components()) {
+                    if let Component::Normal(name) = component {
+                        dir = dir.dirs.entry(name.to_string_lossy().into()).or_default();
+                    }
+                }
+                dir.files.push(idx);
+            }
+        }
+    }
+
+    let mut visited = vec![false; articles.len()];
+    Tree::Dir(
+        String::new(),
+        dir_trees(root, articles, &nested, order, &mut visited),
+    )
+}
+
+fn dir_trees(
+    dir: Dir,
+    articles: &[Article],
+    nested: &HashMap<usize, Vec<usize>>,
+    order: TreeOrder,
+    visited: &mut [bool],
+) -> Vec<Tree> {
+    let mut trees = vec![];
+    for (name, dir) in dir.dirs {
+        let children = dir_trees(dir, articles, nested, order, visited);
+        trees.push(Tree::Dir(name, children));
+    }
+    trees.extend(file_trees(dir.files, articles, nested, order, visited));
+    trees
+}
+
+fn file_trees(
+    mut files: Vec<usize>,
+    articles: &[Article],
+    nested: &HashMap<usize, Vec<usize>>,
+    order: TreeOrder,
+    visited: &mut [bool],
+) -> Vec<Tree> {
+    files.sort_by(|a, b| compare(order, *a, &articles[*a], *b, &articles[*b]));
+
+    let mut trees = vec![];
+    for idx in files {
+        // Guards against items nesting each other when ids collide.
+        if std::mem::replace(&mut visited[idx], true) {
+            continue;
+        }
+        let children = nested.get(&idx).cloned().unwrap_or_default();
+        let children = file_trees(children, articles, nested, order, visited);
+        trees.push(Tree::File(idx, children));
+    }
+    trees
+}
+
+// `path` keeps the order of the files and of the items inside them.
+fn compare(order: TreeOrder, a_idx: usize, a: &Article, b_idx: usize, b: &Article) -> Ordering {
+    let ((a, a_file, _), (b, b_file, _)) = (a, b);
+    match order {
+        TreeOrder::Title => (&a.title, &a.kind, &a.id).cmp(&(&b.title, &b.kind, &b.id)),
+        TreeOrder::Path => (a_file, a_idx).cmp(&(b_file, b_idx)),
+        TreeOrder::Kind => (&a.kind, &a.title, &a.id).cmp(&(&b.kind, &b.title, &b.id)),
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, Hash)]
 pub struct IndexEntry {
     pub kind: String,
     pub id: String,
     pub title: String,
     pub path: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    const ARTICLES: &[(&str, &str)] = &[
+        (
+            "npc.md",
+            r#"+++ npc:0000000b +++
+title = "Zbigniew"
++++
+"#,
+        ),
+        (
+            "sesje/1.md",
+            r#"+++ sesja:0000000a +++
+title = "Sesja 1"
++++
+
+++ npc:0000000c ++
+title = "Borys"
++++
+++ end ++
+
+++ npc:0000000d ++
+title = "Agata"
++++
+++ end ++
+"#,
+        ),
+        (
+            "sesje/2.md",
+            r#"+++ npc:0000000e +++
+title = "Tadek"
++++
+"#,
+        ),
+        (
+            "ambaras.md",
+            r#"+++ miejsce:0000000f +++
+title = "Ambaras"
++++
+"#,
+        ),
+    ];
+
+    fn workspace_index(files: &[(&str, &str)], order: TreeOrder) -> Index {
+        let mut db = test_db(files);
+        db.set_tree_order(order);
+        index(&db, Path::new(""), Path::new("articles"))
+    }
+
+    fn titles(index: &Index, tree: &Tree) -> String {
+        let children = |children: &[Tree]| {
+            children
+                .iter()
+                .map(|child| titles(index, child))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match tree {
+            Tree::Dir(name, dir) => format!("{}/[{}]", name, children(dir)),
+            Tree::File(idx, nested) if nested.is_empty() => index.data[*idx].title.clone(),
+            Tree::File(idx, nested) => format!("{}[{}]", index.data[*idx].title, children(nested)),
+            Tree::None => String::new(),
+        }
+    }
+
+    #[test]
+    fn tree_by_title() {
+        let index = workspace_index(ARTICLES, TreeOrder::Title);
+        assert_eq!(
+            titles(&index, &index.project),
+            "/[sesje/[Sesja 1[Agata, Borys], Tadek], Ambaras, Zbigniew]"
+        );
+        assert_eq!(index.data[0].path, "articles/miejsce/0000000f.html");
+    }
+
+    #[test]
+    fn tree_by_path_and_kind() {
+        let index = workspace_index(ARTICLES, TreeOrder::Path);
+        assert_eq!(
+            titles(&index, &index.project),
+            "/[sesje/[Sesja 1[Borys, Agata], Tadek], Ambaras, Zbigniew]"
+        );
+
+        let index = workspace_index(ARTICLES, TreeOrder::Kind);
+        assert_eq!(
+            titles(&index, &index.project),
+            "/[sesje/[Tadek, Sesja 1[Agata, Borys]], Ambaras, Zbigniew]"
+        );
+
+        assert_eq!(workspace_index(&[], TreeOrder::Path).project, Tree::None);
+    }
+}
@@ -3,9 +3,15 @@ use anyhow::Result;
 use std::path::{Path, PathBuf};
 
 pub mod build;
+pub mod graph;
 pub mod index;
+pub mod new;
+pub mod search;
 pub mod server;
 pub mod span_ext;
+pub mod tables;
+pub mod template;
+pub mod timeline;
 pub mod watch;
 
 #[salsa::database(
@@ -22,6 +28,21 @@ pub struct Database {
 }
 impl salsa::Database for Database {}
 
+#[cfg(test)]
+pub(crate) fn test_db(files: &[(&str, &str)]) -> Database {
+    use build::Builder;
+    use neu_db::Diagnostician;
+    use neu_render::db::Renderer;
+
+    let mut db = Database::default();
+    neu_syntax::db::load_files(&mut db, files);
+    db.set_html_policy(Default::default());
+    db.set_audience(Default::default());
+    db.set_lints(Default::default());
+    db.set_config(Default::default());
+    db
+}
+
 pub fn find_in_ancestors(start: Option<PathBuf>, indicator: impl AsRef<Path>) -> Result<PathBuf> {
     let mut path = match start {
         Some(s) => s,
@@ -1,12 +1,30 @@
 use anyhow::Result;
 use clap::Clap;
 use env_logger::Env;
+use neu_cli::build::Builder;
+use neu_cli::index::TreeOrder;
+use neu_config::{find_workspace, Config, Overrides};
+use neu_render::db::Renderer;
+use neu_render::{Audience, GraphFormat, HtmlPolicy, TimelineGroup};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 pub(crate) use neu_cli::*;
 
 #[derive(Debug, Clap)]
 struct Opts {
+    #[clap(long, default_value = "sanitize")]
+    html: HtmlPolicy,
+
+    #[clap(long, global = true)]
+    audience: Option<Audience>,
+
+    #[clap(long)]
+    lint_orphans: bool,
+
+    #[clap(long, default_value = "title", global = true)]
+    order: TreeOrder,
+
     #[clap(subcommand)]
     command: Command,
 }
@@ -16,21 +34,95 @@ enum Command {
     Build {
         path: Option<PathBuf>,
 
-        #[clap(short, long, default_value = ".neu")]
-        dist: PathBuf,
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
     },
     Watch {
         path: Option<PathBuf>,
 
-        #[clap(short, long, default_value = ".neu")]
-        dist: PathBuf,
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
     },
     Serve {
         path: Option<PathBuf>,
 
-        #[clap(short, long, default_value = ".neu")]
-        dist: PathBuf,
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
     },
+    Graph {
+        path: Option<PathBuf>,
+
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
+
+        #[clap(long, default_value = "dot")]
+        format: GraphFormat,
+
+        #[clap(long)]
+        kind: Vec<String>,
+
+        #[clap(long)]
+        root: Option<String>,
+
+        // Hops from `--root`, following links in either direction.
+        #[clap(long)]
+        depth: Option<usize>,
+
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    Timeline {
+        path: Option<PathBuf>,
+
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
+
+        #[clap(long, default_value = "era")]
+        group: TimelineGroup,
+
+        #[clap(long)]
+        kind: Vec<String>,
+    },
+    Search {
+        query: String,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
+
+        #[clap(long)]
+        kind: Vec<String>,
+    },
+    Clean {
+        path: Option<PathBuf>,
+
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
+    },
+    New {
+        kind: String,
+
+        #[clap(long)]
+        title: Option<String>,
+
+        #[clap(long)]
+        path: Option<PathBuf>,
+
+        #[clap(short, long)]
+        dist: Option<PathBuf>,
+    },
+}
+
+impl Opts {
+    fn overrides(&self, dist: Option<PathBuf>) -> Overrides {
+        Overrides {
+            dist,
+            audience: self.audience,
+            lint_orphans: self.lint_orphans,
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -41,20 +133,25 @@ fn main() -> Result<()> {
     log::debug!("{:?}", opts);
 
     let mut db = Database::default();
+    db.set_html_policy(opts.html);
+    db.set_tree_order(opts.order);
 
-    match opts.command {
+    match opts.command.clone() {
         Command::Build { path, dist } => {
-            let root = find_in_ancestors(path, &dist)?;
-            build::build(&mut db, &root, &dist)?;
+            let (root, config) = workspace(&mut db, path, &opts.overrides(dist))?;
+            build::build(&mut db, &root, &config.dist)?;
         }
         Command::Watch { path, dist } => {
-            let root = find_in_ancestors(path, &dist)?;
-            watch::watch(&mut db, &root, &dist, None)?;
+            let overrides = opts.overrides(dist);
+            let (root, _) = workspace(&mut db, path, &overrides)?;
+            watch::watch(&mut db, &root, &overrides, None)?;
         }
         Command::Serve { path, dist } => {
-            let root = find_in_ancestors(path, &dist)?;
+            let overrides = opts.overrides(dist);
+            let (root, config) = workspace(&mut db, path, &overrides)?;
+            let addr = config.server_addr()?;
             let r = root.clone();
-            let d = dist.clone();
+            let d = config.dist.clone();
             let rt = tokio::runtime::Builder::new()
                 .threaded_scheduler()
                 .enable_all()
@@ -62,11 +159,72 @@ fn main() -> Result<()> {
                 .unwrap();
             let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<()>();
             rt.spawn(async move {
-                server::run(&r, &d, rx).await;
+                server::run(&r, &d, addr, rx).await;
             });
-            watch::watch(&mut db, &root, &dist, Some(tx))?;
+            watch::watch(&mut db, &root, &overrides, Some(tx))?;
+        }
+        Command::Graph {
+            path,
+            dist,
+            format,
+            kind,
+            root: article,
+            depth,
+            output,
+        } => {
+            let (root, _) = workspace(&mut db, path, &opts.overrides(dist))?;
+            graph::graph(
+                &mut db,
+                &root,
+                format,
+                &kind,
+                article.as_deref(),
+                depth,
+                output.as_deref(),
+            )?;
+        }
+        Command::Timeline {
+            path,
+            dist,
+            group,
+            kind,
+        } => {
+            let (root, _) = workspace(&mut db, path, &opts.overrides(dist))?;
+            timeline::timeline(&mut db, &root, group, &kind)?;
+        }
+        Command::Clean { path, dist } => {
+            let (root, config) = workspace(&mut db, path, &opts.overrides(dist))?;
+            build::clean(&root, &config.dist)?;
+        }
+        Command::Search {
+            query,
+            path,
+            dist,
+            kind,
+        } => {
+            let (root, _) = workspace(&mut db, path, &opts.overrides(dist))?;
+            search::search(&mut db, &root, &query, &kind)?;
+        }
+        Command::New {
+            kind,
+            title,
+            path,
+            dist,
+        } => {
+            let (root, _) = workspace(&mut db, None, &opts.overrides(dist))?;
+            new::new(&mut db, &root, &kind, title.as_deref(), path.as_deref())?;
         }
     }
 
     Ok(())
 }
+
+fn workspace(
+    db: &mut Database,
+    path: Option<PathBuf>,
+    overrides: &Overrides,
+) -> Result<(PathBuf, Arc<Config>)> {
+    let root = find_workspace(path, overrides.dist.as_deref())?;
+    let config = overrides.load(&root)?;
+    Ok((root, build::configure(db, config)))
+}
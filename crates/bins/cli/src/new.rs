@@ -0,0 +1,172 @@
+use crate::build::{scan_all, Builder};
+use crate::template::fill;
+use anyhow::{anyhow, Result};
+use neu_eval::Rng;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SKELETON: &str = "+++ {{ kind }}:{{ id }} +++
+title = {{ title_value }}
++++
+
+";
+
+pub fn new(
+    db: &mut dyn Builder,
+    root: &Path,
+    kind: &str,
+    title: Option<&str>,
+    path: Option<&Path>,
+) -> Result<PathBuf> {
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(anyhow!("Invalid article kind `{}`", kind));
+    }
+
+    scan_all(db, root)?;
+    let taken = db
+        .parse_all_mds()
+        .into_iter()
+        .map(|(_, id, _, _)| id)
+        .collect::<HashSet<_>>();
+    let mut rng = Rng::from_time();
+    let id = loop {
+        let id = random_id(&mut rng);
+        if !taken.contains(&id) {
+            break id;
+        }
+    };
+
+    let path = match path {
+        Some(path) if path.is_dir() => path.join(format!("{}.md", id)),
+        Some(path) => path.into(),
+        None => root.join(kind).join(format!("{}.md", id)),
+    };
+    if path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+
+    let skeleton = root
+        .join(&db.config().templates)
+        .join(format!("{}.md", kind));
+    let skeleton = if skeleton.is_file() {
+        std::fs::read_to_string(skeleton)?
+    } else {
+        DEFAULT_SKELETON.into()
+    };
+
+    let title = title.unwrap_or("Untitled");
+    let mut slots = BTreeMap::new();
+    slots.insert("kind", kind.to_string());
+    slots.insert("id", id);
+    slots.insert("title", title.to_string());
+    slots.insert("title_value", string_literal(title));
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, fill(&skeleton, &slots))?;
+
+    println!("Created {}", path.display());
+
+    Ok(path)
+}
+
+fn random_id(rng: &mut Rng) -> String {
+    format!("{:08x}", rng.next_u64() as u32)
+}
+
+fn string_literal(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+    use assert_fs::prelude::*;
+    use neu_syntax::db::Parser;
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(string_literal("Borys"), r#""Borys""#);
+        assert_eq!(
+            string_literal(r#"Borys "the Bold""#),
+            r#""Borys \"the Bold\"""#
+        );
+        assert_eq!(string_literal(r"${hp}\n"), r#""\${hp}\\n""#);
+    }
+
+    #[test]
+    fn random_ids() {
+        let id = random_id(&mut Rng::new(7));
+        assert_eq!(id, random_id(&mut Rng::new(7)));
+        assert_ne!(id, random_id(&mut Rng::new(8)));
+        assert_eq!(id.len(), 8);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn new_article() -> Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let root = temp.path();
+        temp.child("a.md").write_file(Path::new("tests/a.md"))?;
+
+        let mut db = test_db(&[]);
+        let path = new(&mut db, root, "npc", Some("Borys"), None)?;
+
+        assert!(path.starts_with(root.join("npc")));
+        let content = std::fs::read_to_string(&path)?;
+        assert!(content.starts_with("+++ npc:"));
+        assert!(content.contains("title = \"Borys\""));
+
+        scan_all(&mut db, root)?;
+        let ids = db
+            .parse_all_mds()
+            .into_iter()
+            .map(|(kind, id, _, _)| format!("{}:{}", kind, id))
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains("test:1234aaaa"));
+
+        temp.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn new_article_from_template() -> Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let root = temp.path();
+        temp.child("templates").child("npc.md").write_str(
+            "+++ {{ kind }}:{{ id }} +++\ntitle = {{ title_value }}\nrace = \"dwarf\"\n+++\n\n# {{ title }}\n",
+        )?;
+
+        let mut db = test_db(&[]);
+        let path = new(&mut db, root, "npc", Some("Borys"), None)?;
+
+        let content = std::fs::read_to_string(&path)?;
+        assert!(content.contains("race = \"dwarf\""));
+        assert!(content.ends_with("# Borys\n"));
+
+        temp.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn new_article_refuses_existing_file() -> Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        let root = temp.path();
+        let existing = temp.child("a.md");
+        existing.write_file(Path::new("tests/a.md"))?;
+
+        let mut db = test_db(&[]);
+        assert!(new(&mut db, root, "npc", None, Some(existing.path())).is_err());
+        assert!(new(&mut db, root, "n/pc", None, None).is_err());
+
+        temp.close()?;
+        Ok(())
+    }
+}
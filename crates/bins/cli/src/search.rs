@@ -0,0 +1,358 @@
+use crate::build::{scan_all, Builder};
+use anyhow::Result;
+use neu_render::{escape, plain_text, visible_fields};
+use neu_syntax::ast::ArticleItem;
+use neu_syntax::db::FileId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+const TITLE_WEIGHT: u32 = 5;
+const FIELD_WEIGHT: u32 = 2;
+const BODY_WEIGHT: u32 = 1;
+const SNIPPET_WORDS: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchDoc {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub fields: String,
+    pub body: String,
+}
+
+// Inverted index from lowercase words to the documents containing them and their weight there.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub docs: Vec<SearchDoc>,
+    pub terms: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub score: u32,
+    // Escaped HTML with matches wrapped in `<mark>`.
+    pub title_html: String,
+    pub snippet: String,
+    #[serde(skip)]
+    pub excerpt: String,
+}
+
+impl SearchIndex {
+    pub fn add(&mut self, doc: SearchDoc) {
+        let idx = self.docs.len();
+        let mut weights = BTreeMap::new();
+        for (text, weight) in &[
+            (&doc.title, TITLE_WEIGHT),
+            (&doc.fields, FIELD_WEIGHT),
+            (&doc.body, BODY_WEIGHT),
+        ] {
+            for term in terms(text) {
+                *weights.entry(term).or_insert(0) += weight;
+            }
+        }
+        for (term, weight) in weights {
+            self.terms.entry(term).or_default().push((idx, weight));
+        }
+        self.docs.push(doc);
+    }
+
+    // Every query word has to match, whole words score double over prefixes.
+    pub fn search(&self, query: &str, kinds: &[String]) -> Vec<SearchHit> {
+        let query = terms(query);
+        let mut scores: Option<HashMap<usize, u32>> = None;
+        for term in &query {
+            let mut found = HashMap::new();
+            let matching = self
+                .terms
+                .range(term.clone()..)
+                .take_while(|(indexed, _)| indexed.starts_with(term.as_str()));
+            for (indexed, postings) in matching {
+                let bonus = if indexed == term { 2 } else { 1 };
+                for (doc, weight) in postings {
+                    *found.entry(*doc).or_insert(0) += weight * bonus;
+                }
+            }
+            scores = Some(match scores {
+                None => found,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(doc, score)| Some((doc, score + found.get(&doc)?)))
+                    .collect(),
+            });
+        }
+
+        let mut hits = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(doc, score)| (&self.docs[doc], score))
+            .filter(|(doc, _)| kinds.is_empty() || kinds.contains(&doc.kind))
+            .map(|(doc, score)| {
+                let text = [&doc.body, &doc.fields]
+                    .iter()
+                    .find(|text| first_match(&words(text), text, &query).is_some())
+                    .copied()
+                    .unwrap_or(&doc.body);
+                SearchHit {
+                    kind: doc.kind.clone(),
+                    id: doc.id.clone(),
+                    title: doc.title.clone(),
+                    score,
+                    title_html: excerpt(&doc.title, &query, usize::MAX, true),
+                    snippet: excerpt(text, &query, SNIPPET_WORDS, true),
+                    excerpt: excerpt(text, &query, SNIPPET_WORDS, false),
+                }
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| (&a.title, &a.kind, &a.id).cmp(&(&b.title, &b.kind, &b.id)))
+        });
+        hits
+    }
+}
+
+fn terms(text: &str) -> Vec<String> {
+    words(text)
+        .into_iter()
+        .map(|(start, end)| text[start..end].to_lowercase())
+        .collect()
+}
+
+// Byte ranges of the alphanumeric runs in the text.
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut words = vec![];
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(from)) => {
+                words.push((from, idx));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, text.len()));
+    }
+    words
+}
+
+fn is_match(word: &str, query: &[String]) -> bool {
+    let word = word.to_lowercase();
+    query.iter().any(|term| word.starts_with(term.as_str()))
+}
+
+fn first_match(words: &[(usize, usize)], text: &str, query: &[String]) -> Option<usize> {
+    words
+        .iter()
+        .position(|(start, end)| is_match(&text[*start..*end], query))
+}
+
+// A window of words around the first match, matches are marked with `<mark>` or `*`.
+fn excerpt(text: &str, query: &[String], len: usize, html: bool) -> String {
+    let words = words(text);
+    if words.is_empty() {
+        return String::new();
+    }
+    let first = first_match(&words, text, query).unwrap_or(0);
+    let from = first.saturating_sub(len / 4);
+    let to = from.saturating_add(len).min(words.len());
+
+    let plain = |text: &str| {
+        let text = text.replace('\n', " ");
+        if html {
+            escape(&text)
+        } else {
+            text
+        }
+    };
+    let mut output = String::new();
+    let mut rest = 0;
+    if from > 0 {
+        output.push('…');
+        rest = words[from].0;
+    }
+    for (start, end) in &words[from..to] {
+        output.push_str(&plain(&text[rest..*start]));
+        let word = plain(&text[*start..*end]);
+        match (is_match(&text[*start..*end], query), html) {
+            (true, true) => output.push_str(&format!("<mark>{}</mark>", word)),
+            (true, false) => output.push_str(&format!("*{}*", word)),
+            (false, _) => output.push_str(&word),
+        }
+        rest = *end;
+    }
+    if to < words.len() {
+        output.push('…');
+    } else {
+        output.push_str(&plain(&text[rest..]));
+    }
+    output
+}
+
+fn document(
+    db: &dyn Builder,
+    kind: &str,
+    id: &str,
+    path: FileId,
+    article_item: &ArticleItem,
+) -> SearchDoc {
+    let mut fields = article_item
+        .strukt
+        .and_then(|strukt| db.eval(path, strukt).value.clone())
+        .and_then(|value| value.into_struct())
+        .map(|fields| visible_fields(fields, db.audience()))
+        .unwrap_or_default();
+    let title = fields
+        .shift_remove("title")
+        .map(|title| plain_text(&title))
+        .unwrap_or_else(|| "???".into());
+    fields.shift_remove("layout");
+    let fields = fields
+        .values()
+        .filter(|value| !value.is_null())
+        .map(plain_text)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    SearchDoc {
+        kind: kind.into(),
+        id: id.into(),
+        title,
+        fields,
+        body: db.article_text(path, article_item.clone()).to_string(),
+    }
+}
+
+pub(crate) fn search_path(root: &Path, dist: &Path) -> PathBuf {
+    root.join(dist).join("search.json")
+}
+
+// Used by the server, which only has the build output.
+pub fn search_file(path: &Path, query: &str, kinds: &[String]) -> Vec<SearchHit> {
+    let index = std::fs::read(path)
+        .ok()
+        .and_then(|file| serde_json::from_slice::<SearchIndex>(&file).ok())
+        .unwrap_or_default();
+    index.search(query, kinds)
+}
+
+pub(crate) fn search_index(db: &dyn Builder) -> SearchIndex {
+    let mut index = SearchIndex::default();
+    for (kind, id, path, article_item) in db.parse_all_mds() {
+        if !db.is_hidden(kind.clone(), id.clone()) {
+            index.add(document(db, &kind, &id, path, &article_item));
+        }
+    }
+    index
+}
+
+pub fn search(db: &mut dyn Builder, root: &Path, query: &str, kinds: &[String]) -> Result<()> {
+    scan_all(db, root)?;
+
+    for hit in search_index(db).search(query, kinds) {
+        println!("{}:{}  {}  ({})", hit.kind, hit.id, hit.title, hit.score);
+        if !hit.excerpt.is_empty() {
+            println!("    {}", hit.excerpt);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    const SEARCH_ARTICLES: &[(&str, &str)] = &[
+        (
+            "npc.md",
+            r#"+++ npc:0123abcd +++
+title = "Borys"
+job = "kowal"
++++
+
+Kowal z wioski, zna \<Młot\>.
+"#,
+        ),
+        (
+            "sesja.md",
+            r#"+++ sesja:a88907fd +++
+title = "Pośród Pradawnych Drzew"
++++
+
+Drużyna spotyka Borysa w kuźni.
+"#,
+        ),
+        (
+            "miejsce.md",
+            r#"+++ miejsce:0000aaaa +++
+title = "Kuźnia"
++++
+
+Młot i kowadło.
+"#,
+        ),
+    ];
+
+    fn index() -> SearchIndex {
+        search_index(&test_db(SEARCH_ARTICLES))
+    }
+
+    #[test]
+    fn ranked_search() {
+        let index = index();
+        let hits = index.search("bor", &[]);
+        let found = hits
+            .iter()
+            .map(|hit| format!("{}:{} {}", hit.kind, hit.id, hit.score))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec!["npc:0123abcd 5", "sesja:a88907fd 1"]);
+        assert_eq!(hits[0].title_html, "<mark>Borys</mark>");
+        assert_eq!(
+            hits[1].snippet,
+            "Drużyna spotyka <mark>Borysa</mark> w kuźni. "
+        );
+
+        assert_eq!(
+            index.search("kowal", &[])[0].score,
+            2 * (FIELD_WEIGHT + BODY_WEIGHT)
+        );
+        assert_eq!(index.search("młot kowal", &[]).len(), 1);
+        assert!(index.search("smok", &[]).is_empty());
+        assert!(index.search("", &[]).is_empty());
+    }
+
+    #[test]
+    fn kind_filter_and_snippets() {
+        let index = index();
+        let hits = index.search("młot", &["npc".to_string()]);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].snippet,
+            "Kowal z wioski, zna &lt;<mark>Młot</mark>&gt;. "
+        );
+        assert_eq!(hits[0].excerpt, "Kowal z wioski, zna <*Młot*>. ");
+
+        let long = (0..40)
+            .map(|i| format!("w{}", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let all = terms(&long);
+        assert_eq!(
+            excerpt(&long, &["w20".to_string()], 4, false),
+            format!("…{} *w20* {}…", all[19], all[21..23].join(" "))
+        );
+
+        let json = serde_json::to_string(&index).unwrap();
+        let parsed: SearchIndex = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, index);
+    }
+}
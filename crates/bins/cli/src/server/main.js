@@ -15,6 +15,10 @@ function GetIndex(setIndex, by, searched) {
             ;
             if(!by) {
                 result = data.data.filter(filter);
+            } else if(by === 'project') {
+                result = ProjectGroups(data.project, data.data)
+                    .map(group => [group[0], group[1].filter(filter)])
+                    .filter(group => group[1].length > 0);
             } else {
                 const idx = data[by];
                 result = [];
@@ -34,6 +38,26 @@ function GetIndex(setIndex, by, searched) {
         });
 }
 
+// One group per directory, nested items follow their parent with a bigger depth.
+function ProjectGroups(tree, entries) {
+    const groups = [];
+    const walk = (node, dir, group, depth) => {
+        if(node.Dir) {
+            const [name, children] = node.Dir;
+            const path = dir && name ? `${dir}/${name}` : (dir || name);
+            const inner = [];
+            groups.push([path || '/', inner]);
+            children.forEach(child => walk(child, path, inner, 0));
+        } else if(node.File) {
+            const [idx, children] = node.File;
+            group.push({ ...entries[idx], depth });
+            children.forEach(child => walk(child, dir, group, depth + 1));
+        }
+    };
+    walk(tree, '', null, 0);
+    return groups.filter(group => group[1].length > 0);
+}
+
 // GUI
 
 const { useState, useEffect, createElement } = React;
@@ -41,7 +65,8 @@ const e = createElement;
 
 const SidebarTabs = [
     { icon: 'translate', by: 'abc' },
-    { icon: 'view_stream', by: 'kind' }
+    { icon: 'view_stream', by: 'kind' },
+    { icon: 'account_tree', by: 'project' }
 ];
 
 function useHotReload() {
@@ -152,6 +177,7 @@ function IndexEntry({article, setArticle, entry, scrollState}) {
     return e('div', {
         className: 'index-entry' + (isActive ? ' active' : ''),
         id: `${entry.kind}_${entry.id}_idx`,
+        style: entry.depth ? { paddingLeft: `${1 + entry.depth}em` } : undefined,
         onClick: () => { setArticle({ kind: entry.kind, id: entry.id}) }
     }, [
         e('span', {key: 'title' }, entry.title),
@@ -1,10 +1,12 @@
 use futures::{FutureExt, StreamExt};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::sync::RwLock;
+use warp::http::StatusCode;
 use warp::ws::Message;
 use warp::Filter;
 
@@ -31,7 +33,7 @@ static NEXT_USER_ID: AtomicUsize = AtomicUsize::new(1);
 
 type WsSockets = Arc<RwLock<HashMap<usize, UnboundedSender<Result<Message, warp::Error>>>>>;
 
-pub async fn run(root: &Path, dist: &Path, hot_rx: UnboundedReceiver<()>) {
+pub async fn run(root: &Path, dist: &Path, addr: SocketAddr, hot_rx: UnboundedReceiver<()>) {
     let neu = warp::path("neu").and(warp::fs::dir(root.join(dist)));
 
     let index = warp::path::end().map(|| warp::reply::html(resource!("index.html")));
@@ -64,6 +66,40 @@ pub async fn run(root: &Path, dist: &Path, hot_rx: UnboundedReceiver<()>) {
     let icons_woff2 =
         warp::path!("static" / "Icons-Regular.woff2").map(|| resource!("Icons-Regular.woff2"));
 
+    let articles_path = root.join(dist).join("articles");
+    let roll = warp::path!("roll" / String / String / String).map(
+        move |kind: String, id: String, field: String| {
+            let rolled = crate::tables::roll(&articles_path, &kind, &id, &field);
+            match rolled {
+                Some(rolled) => {
+                    warp::reply::with_status(warp::reply::json(&rolled), StatusCode::OK)
+                }
+                None => {
+                    let error = format!("Couldn't find table {}:{}/{}", kind, id, field);
+                    warp::reply::with_status(warp::reply::json(&error), StatusCode::NOT_FOUND)
+                }
+            }
+        },
+    );
+
+    let search_path = crate::search::search_path(root, dist);
+    let search = warp::path!("search")
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |params: HashMap<String, String>| {
+            let query = params.get("q").map(String::as_str).unwrap_or_default();
+            let kinds = params
+                .get("kind")
+                .map(|kinds| {
+                    kinds
+                        .split(',')
+                        .filter(|kind| !kind.is_empty())
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            warp::reply::json(&crate::search::search_file(&search_path, query, &kinds))
+        });
+
     let ws_sockets: WsSockets = Default::default();
 
     let wss = ws_sockets.clone();
@@ -112,10 +148,12 @@ pub async fn run(root: &Path, dist: &Path, hot_rx: UnboundedReceiver<()>) {
         .or(icons_woff)
         .or(icons_woff2)
         .or(hotreload)
+        .or(roll)
+        .or(search)
         .or(content)
         .or(index);
 
-    warp::serve(routes).run(([127, 0, 0, 1], 3000)).await;
+    warp::serve(routes).run(addr).await;
 }
 
 /*
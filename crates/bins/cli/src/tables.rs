@@ -0,0 +1,130 @@
+use neu_eval::{Rng, Table, Value};
+use neu_render::{value_html, HtmlPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Random tables are exported next to the article so the server can roll them without the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableExport {
+    pub die: u32,
+    pub entries: Vec<EntryExport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryExport {
+    pub from: u32,
+    pub to: u32,
+    pub html: String,
+    pub table: Option<Box<TableExport>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rolled {
+    pub rolls: Vec<u32>,
+    pub html: String,
+}
+
+impl TableExport {
+    pub fn new(table: &Table, policy: HtmlPolicy) -> Self {
+        Self {
+            die: table.die(),
+            entries: table
+                .entries
+                .iter()
+                .map(|entry| EntryExport {
+                    from: entry.from,
+                    to: entry.to,
+                    html: value_html(&entry.value, policy),
+                    table: match &entry.value {
+                        Value::Table(nested) => Some(Box::new(Self::new(nested, policy))),
+                        _ => None,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    pub fn roll(&self, mut die: impl FnMut(u32) -> u32) -> Rolled {
+        let mut rolls = vec![];
+        let mut table = self;
+        loop {
+            let roll = die(table.die);
+            rolls.push(roll);
+            let entry = table
+                .entries
+                .iter()
+                .find(|entry| entry.from <= roll && roll <= entry.to);
+            match entry {
+                Some(EntryExport {
+                    table: Some(nested),
+                    ..
+                }) => table = nested,
+                Some(entry) => {
+                    return Rolled {
+                        rolls,
+                        html: entry.html.clone(),
+                    }
+                }
+                None => {
+                    return Rolled {
+                        rolls,
+                        html: String::new(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn tables_path(articles_path: &Path, kind: &str, id: &str) -> PathBuf {
+    articles_path.join(kind).join(format!("{}.tables.json", id))
+}
+
+pub fn roll(articles_path: &Path, kind: &str, id: &str, field: &str) -> Option<Rolled> {
+    let valid = |part: &str| part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid(kind) || !valid(id) {
+        return None;
+    }
+    let file = std::fs::read(tables_path(articles_path, kind, id)).ok()?;
+    let tables: BTreeMap<String, TableExport> = serde_json::from_slice(&file).ok()?;
+    let mut rng = Rng::from_time();
+    Some(tables.get(field)?.roll(|die| rng.roll(die)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+    use neu_eval::db::Evaluator;
+    use neu_syntax::db::{FileKind, Parser};
+
+    const ENCOUNTERS: &str =
+        r#"table([("1-3", "goblins"), (4, table([(1, "copper"), (2, "<silver>")]))])"#;
+
+    #[test]
+    fn roll_nested_tables() {
+        let db = test_db(&[("encounters.neu", ENCOUNTERS)]);
+        let file = db.file_id(("encounters.neu".into(), FileKind::Neu));
+        let encounters = match db.eval(file, db.parse_syntax(file).root).value.clone() {
+            Some(Value::Table(table)) => table,
+            value => panic!("Expected a table, got {:?}", value),
+        };
+        let export = TableExport::new(&encounters, HtmlPolicy::default());
+        assert_eq!(export.die, 4);
+
+        let mut rolls = vec![4, 2].into_iter();
+        assert_eq!(
+            export.roll(|_| rolls.next().unwrap()),
+            Rolled {
+                rolls: vec![4, 2],
+                html: "&lt;silver&gt;".into()
+            }
+        );
+        assert_eq!(export.roll(|_| 2).html, "goblins");
+
+        let json = serde_json::to_string(&export).unwrap();
+        let parsed: TableExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, export);
+    }
+}
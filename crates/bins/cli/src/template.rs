@@ -0,0 +1,98 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const DEFAULT_ARTICLE: &str = include_str!("templates/article.html");
+const DEFAULT_INDEX: &str = include_str!("templates/index.html");
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Templates {
+    kinds: BTreeMap<String, String>,
+    article: Option<String>,
+    index: Option<String>,
+}
+
+impl Templates {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut templates = Self::default();
+        if !dir.is_dir() {
+            return Ok(templates);
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("html") {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let template = std::fs::read_to_string(&path)?;
+            match name.as_str() {
+                "article" => templates.article = Some(template),
+                "index" => templates.index = Some(template),
+                _ => {
+                    templates.kinds.insert(name, template);
+                }
+            }
+        }
+        Ok(templates)
+    }
+
+    pub fn article(&self, kind: &str) -> &str {
+        self.kinds
+            .get(kind)
+            .or_else(|| self.article.as_ref())
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_ARTICLE)
+    }
+
+    pub fn index(&self) -> &str {
+        self.index.as_deref().unwrap_or(DEFAULT_INDEX)
+    }
+}
+
+pub fn fill(template: &str, slots: &BTreeMap<&str, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..end].trim();
+        match slots.get(name) {
+            Some(value) => output.push_str(value),
+            None => log::warn!("Unknown template slot `{}`", name),
+        }
+        rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_slots() {
+        let slots = vec![
+            ("title", "Foo".to_string()),
+            ("body", "<p>Bar</p>".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            fill("<h1>{{ title }}</h1>{{body}}{{ missing }}", &slots),
+            "<h1>Foo</h1><p>Bar</p>"
+        );
+    }
+
+    #[test]
+    fn fill_unclosed_slot() {
+        let slots = BTreeMap::new();
+        assert_eq!(fill("a {{ b", &slots), "a {{ b");
+    }
+}
@@ -0,0 +1,40 @@
+use crate::build::{scan_all, Builder};
+use anyhow::Result;
+use neu_render::{timeline_sections, TimelineGroup};
+use std::path::Path;
+
+pub fn timeline(
+    db: &mut dyn Builder,
+    root: &Path,
+    group: TimelineGroup,
+    kinds: &[String],
+) -> Result<()> {
+    scan_all(db, root)?;
+
+    let calendar = db.calendar();
+    let entries = db.timeline();
+    for (i, section) in timeline_sections(&entries, &calendar, group, kinds)
+        .iter()
+        .enumerate()
+    {
+        if i > 0 {
+            println!();
+        }
+        match (&section.title, &section.article) {
+            (Some(title), Some((kind, id))) => println!("{} ({}:{})", title, kind, id),
+            (Some(title), None) => println!("{}", title),
+            _ => (),
+        }
+        for entry in &section.entries {
+            println!(
+                "  {:<24} {}:{}  {}",
+                calendar.format(&entry.date),
+                entry.kind,
+                entry.id,
+                entry.title
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,25 +1,53 @@
 use crate::build::Builder;
 use anyhow::Result;
 use ignore::gitignore::GitignoreBuilder;
+use neu_config::{Config, Overrides, CONFIG_FILE};
 use neu_syntax::db::{FileId, FileKind};
 use notify::DebouncedEvent;
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::mpsc::UnboundedSender;
 
+fn all_files(db: &dyn Builder, kind: FileKind) -> Arc<HashSet<FileId>> {
+    match kind {
+        FileKind::Md => db.all_mds(),
+        FileKind::Neu => db.all_neu(),
+    }
+}
+
+fn set_all_files(db: &mut dyn Builder, kind: FileKind, files: HashSet<FileId>) {
+    match kind {
+        FileKind::Md => db.set_all_mds(Arc::new(files)),
+        FileKind::Neu => db.set_all_neu(Arc::new(files)),
+    }
+}
+
+// An invalid `neu.toml` is reported and the previous config stays in place.
+fn reload_config(db: &mut dyn Builder, root: &Path, overrides: &Overrides) -> Option<Arc<Config>> {
+    match overrides.load(root) {
+        Ok(config) => Some(crate::build::configure(db, config)),
+        Err(e) => {
+            eprintln!("{:?}", e);
+            None
+        }
+    }
+}
+
 pub fn watch(
     db: &mut dyn Builder,
     root: &Path,
-    dist: &Path,
+    overrides: &Overrides,
     mut hot_tx: Option<UnboundedSender<()>>,
 ) -> Result<()> {
     use notify::{RecommendedWatcher, RecursiveMode, Watcher};
     use std::sync::mpsc::channel;
-    use std::time::Duration;
 
     let (tx, rx) = channel();
 
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_secs(2))?;
+    let mut config = db.config();
+    let config_path = root.join(CONFIG_FILE);
+    let mut watcher: RecommendedWatcher = Watcher::new(tx.clone(), config.debounce)?;
 
     watcher.watch(root, RecursiveMode::Recursive)?;
 
@@ -29,7 +57,7 @@ pub fn watch(
     let gitignore = ignore_builder.build()?;
 
     crate::build::scan_all(db, &root)?;
-    db.build_all(root.into(), dist.into())?;
+    db.build_all(root.into(), config.dist.clone())?;
 
     println!("Build finished. Watching directory");
     let mut hotreload = || {
@@ -41,39 +69,75 @@ pub fn watch(
     loop {
         let event = rx.recv()?;
         match event {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Remove(path)
+                if path == config_path =>
+            {
+                println!("\n\n\nConfig changed: {:?}", path);
+                if let Some(reloaded) = reload_config(db, root, overrides) {
+                    config = reloaded;
+                    watcher = Watcher::new(tx.clone(), config.debounce)?;
+                    watcher.watch(root, RecursiveMode::Recursive)?;
+                    crate::build::scan_all(db, root)?;
+                    db.build_all(root.into(), config.dist.clone())?;
+                    hotreload();
+                }
+            }
             DebouncedEvent::NoticeWrite(path)
             | DebouncedEvent::Create(path)
             | DebouncedEvent::Write(path) => {
                 let is_dir = path.is_dir();
                 let matches = gitignore.matched_path_or_any_parents(&path, is_dir);
-                if !matches.is_ignore() && path.exists() {
+                if config.is_template(root, &path) {
+                    println!("\n\n\nTemplate changed: {:?}", path);
+                    crate::build::scan_templates(db, root)?;
+                    db.build_all(root.into(), config.dist.clone())?;
+                    hotreload();
+                    continue;
+                }
+                let kind = match crate::build::file_kind(&path) {
+                    Some(kind) if config.is_source(root, &path) => kind,
+                    _ => continue,
+                };
+                if !matches.is_ignore() && path.is_file() {
                     println!("\n\n\nChanged: {:?}", path);
                     let path_str = path.display().to_string();
-                    let file_id: FileId = db.file_id((path_str, FileKind::Md));
-                    //TODO: Only mds for now
-                    let mut all_mds = (*db.all_mds()).clone();
-                    if !all_mds.contains(&file_id) {
+                    let file_id: FileId = db.file_id((path_str, kind));
+                    let mut files = (*all_files(db, kind)).clone();
+                    if !files.contains(&file_id) {
                         println!("File did not existed");
-                        all_mds.insert(file_id);
-                        db.set_all_mds(Arc::new(all_mds));
+                        files.insert(file_id);
+                        set_all_files(db, kind, files);
                     }
                     let file = std::fs::read_to_string(path)?;
                     db.set_input(file_id, Arc::new(file));
-                    db.build_all(root.into(), dist.into())?;
+                    db.build_all(root.into(), config.dist.clone())?;
                     hotreload();
                 }
             }
             DebouncedEvent::NoticeRemove(path) | DebouncedEvent::Remove(path) => {
                 let is_dir = path.is_dir();
                 let matches = gitignore.matched_path_or_any_parents(&path, is_dir);
+                if config.is_template(root, &path) {
+                    println!("\n\n\nTemplate removed: {:?}", path);
+                    crate::build::scan_templates(db, root)?;
+                    db.build_all(root.into(), config.dist.clone())?;
+                    hotreload();
+                    continue;
+                }
+                let kind = match crate::build::file_kind(&path) {
+                    Some(kind) if config.is_source(root, &path) => kind,
+                    _ => continue,
+                };
                 if !matches.is_ignore() && !path.exists() {
-                    let file_id = db.file_id((path.display().to_string(), FileKind::Md));
-                    let mut all_mds = (*db.all_mds()).clone();
-                    if all_mds.contains(&file_id) {
+                    let file_id = db.file_id((path.display().to_string(), kind));
+                    let mut files = (*all_files(db, kind)).clone();
+                    if files.contains(&file_id) {
                         println!("\n\n\nRemoved: {:?}", path);
-                        all_mds.remove(&file_id);
-                        db.set_all_mds(Arc::new(all_mds));
-                        db.build_all(root.into(), dist.into())?;
+                        files.remove(&file_id);
+                        set_all_files(db, kind, files);
+                        db.build_all(root.into(), config.dist.clone())?;
                         hotreload();
                     }
                 }
@@ -82,14 +146,14 @@ pub fn watch(
                 //todo!("Rename!");
                 log::warn!("Not implemented yet: Rename");
                 crate::build::scan_all(db, &root)?;
-                db.build_all(root.into(), dist.into())?;
+                db.build_all(root.into(), config.dist.clone())?;
                 hotreload();
             }
             DebouncedEvent::Chmod(_) | DebouncedEvent::Error(_, _) => (),
             DebouncedEvent::Rescan => {
                 println!("Have to rescan");
                 crate::build::scan_all(db, &root)?;
-                db.build_all(root.into(), dist.into())?;
+                db.build_all(root.into(), config.dist.clone())?;
                 hotreload();
             }
         }
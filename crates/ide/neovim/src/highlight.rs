@@ -9,7 +9,7 @@ impl NodeHighlight for Node {
     fn highlight(&self) -> Option<&'static str> {
         if self.is(Nodes::Error) {
             Some("Error")
-        } else if self.is(Nodes::Number) {
+        } else if self.is_any(&[Nodes::Number, Nodes::Date]) {
             Some("Float")
         } else if self.is(Nodes::Boolean) {
             Some("Boolean")
@@ -27,7 +27,7 @@ impl NodeHighlight for Node {
             Some("Operator")
         } else if self.is_any(&[Nodes::String, Nodes::Markdown]) && !self.is(Nodes::Md_Value) {
             Some("String")
-        } else if self.is(Nodes::Md_Link) {
+        } else if self.is_any(&[Nodes::Md_Link, Nodes::Md_WikiLink]) {
             Some("Underlined")
         } else if self.is(Nodes::Identifier) {
             Some("Identifier")
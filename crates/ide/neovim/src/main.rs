@@ -1,7 +1,10 @@
 use anyhow::{Context, Error, Result};
 use env_logger::Env;
+use neu_config::{find_workspace, Config};
+use neu_db::Diagnostician;
 use neu_nvim::handler::NeovimHandler;
 use neu_nvim::{Database, Message};
+use neu_render::db::Renderer;
 use neu_syntax::db::Parser;
 use nvim_rs::create::tokio as create;
 use std::sync::Arc;
@@ -17,6 +20,16 @@ async fn main() -> Result<()> {
 
         db.set_all_neu(Default::default());
         db.set_all_mds(Default::default());
+        db.set_html_policy(Default::default());
+        // Outside of a workspace, or with a broken `neu.toml`, the defaults still work.
+        let config = find_workspace(None, None)
+            .and_then(|root| Config::load(&root))
+            .unwrap_or_else(|e| {
+                log::warn!("{:?}", e);
+                Config::default()
+            });
+        db.set_audience(config.audience);
+        db.set_lints(config.lints);
 
         while let Some(msg) = rx.recv().ok() {
             match msg {
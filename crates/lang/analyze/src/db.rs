@@ -1,23 +1,39 @@
 use crate::Mention;
 use neu_canceled::Canceled;
-use neu_syntax::db::Parser;
+use neu_parser::NodeId;
+use neu_syntax::db::{FileId, Parser};
 
 #[salsa::query_group(AnalyzerDatabase)]
 pub trait Analyzer: salsa::Database + Parser {
     fn all_mentions(&self) -> Vec<Mention>;
+    fn all_mention_nodes(&self) -> Vec<(FileId, NodeId, Mention)>;
 }
 
 fn all_mentions(db: &dyn Analyzer) -> Vec<Mention> {
     Canceled::cancel_if(db.salsa_runtime());
 
+    db.all_mention_nodes()
+        .into_iter()
+        .map(|(_path, _node, mention)| mention)
+        .collect()
+}
+
+fn all_mention_nodes(db: &dyn Analyzer) -> Vec<(FileId, NodeId, Mention)> {
+    Canceled::cancel_if(db.salsa_runtime());
+
     let parsed = db.parse_all_mds();
 
     let mut mentions = vec![];
     for (_kind, _id, path, article_item) in parsed {
         let input = db.input(path);
         let parsed = db.parse_syntax(path);
-        let mut arena = parsed.arena.clone();
-        let _ = crate::find_mentions(db, article_item, &mut arena, &input, &mut mentions);
+        let mut found = vec![];
+        let _ = crate::find_mentions(db, article_item, &parsed.arena, &input, &mut found);
+        mentions.extend(
+            found
+                .into_iter()
+                .map(|(node, mention)| (path, node, mention)),
+        );
     }
 
     mentions
@@ -2,9 +2,18 @@ use crate::db::Analyzer;
 use neu_parser::{Arena, Children, Node, NodeId};
 use neu_syntax::ast::{ArticleItem, ArticleRef, Ast};
 use neu_syntax::Nodes;
+use once_cell::sync::Lazy;
 use regex::Regex;
 
 pub mod db;
+mod slug;
+
+pub use slug::{slug, Slugs};
+
+const SNIPPET_LEN: usize = 120;
+
+static LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([a-z_A-Z0-9]+):([0-9A-Fa-f]{8})(?:#([\w-]+))?$").expect("Regex"));
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Mention {
@@ -12,6 +21,12 @@ pub struct Mention {
     pub orig_id: String,
     pub kind: String,
     pub id: String,
+    pub section: Option<String>,
+    // Slug of the heading above the mention, numbered the same way as the rendered anchors.
+    pub anchor: Option<String>,
+    pub snippet: Option<String>,
+    // Written in a `++ secret ++` block or with a `secret:` prefix.
+    pub secret: bool,
 }
 impl Mention {
     pub fn new(
@@ -25,76 +40,247 @@ impl Mention {
             orig_id: orig_id.into(),
             kind: kind.into(),
             id: id.into(),
+            section: None,
+            anchor: None,
+            snippet: None,
+            secret: false,
         }
     }
+
+    pub fn with_section(mut self, section: Option<&str>) -> Self {
+        self.section = section.map(Into::into);
+        self
+    }
+
+    pub fn with_context(mut self, anchor: Option<&str>, snippet: Option<String>) -> Self {
+        self.anchor = anchor.map(Into::into);
+        self.snippet = snippet;
+        self
+    }
+
+    pub fn with_secret(mut self, secret: bool) -> Self {
+        self.secret = self.secret || secret;
+        self
+    }
 }
 
-fn find_mentions_in_md(
-    _db: &dyn Analyzer,
-    node: &Node,
-    nodes: &Arena,
-    input: &str,
-    orig_kind: &str,
-    orig_id: &str,
-    mentions: &mut Vec<Mention>,
+struct Scope<'a> {
+    nodes: &'a Arena,
+    input: &'a str,
+    orig_kind: &'a str,
+    orig_id: &'a str,
+    anchor: Option<String>,
+    slugs: Slugs,
+    secret: bool,
+}
+
+impl<'a> Scope<'a> {
+    fn mention(&self, kind: &str, id: &str, block: Option<&Node>) -> Mention {
+        let snippet = block.and_then(|block| snippet(block, self.nodes, self.input));
+        Mention::new(self.orig_kind, self.orig_id, kind, id)
+            .with_context(self.anchor.as_deref(), snippet)
+            .with_secret(self.secret)
+    }
+
+    fn heading(&mut self, heading: &Node) {
+        let text = text(heading, self.nodes, self.input);
+        self.anchor = Some(self.slugs.add(&text));
+    }
+}
+
+fn is_heading(node: &Node) -> bool {
+    [
+        Nodes::Md_H1,
+        Nodes::Md_H2,
+        Nodes::Md_H3,
+        Nodes::Md_H4,
+        Nodes::Md_H5,
+        Nodes::Md_H6,
+    ]
+    .iter()
+    .any(|name| node.is(*name))
+}
+
+fn is_block(node: &Node) -> bool {
+    is_heading(node)
+        || [
+            Nodes::Md_Paragraph,
+            Nodes::Md_ListItem,
+            Nodes::Md_TableCell,
+            Nodes::Md_TableHeadCell,
+        ]
+        .iter()
+        .any(|name| node.is(*name))
+}
+
+fn text(block: &Node, nodes: &Arena, input: &str) -> String {
+    let mut parts = vec![];
+    collect_text(block, nodes, input, &mut parts);
+    parts.sort_by_key(|(start, _)| *start);
+
+    let text = parts.into_iter().map(|(_, part)| part).collect::<String>();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn snippet(block: &Node, nodes: &Arena, input: &str) -> Option<String> {
+    let text = text(block, nodes, input);
+    if text.is_empty() {
+        None
+    } else if text.chars().count() > SNIPPET_LEN {
+        let cut = text.chars().take(SNIPPET_LEN).collect::<String>();
+        Some(format!("{}…", cut.trim_end()))
+    } else {
+        Some(text)
+    }
+}
+
+fn collect_text(node: &Node, nodes: &Arena, input: &str, parts: &mut Vec<(usize, String)>) {
+    for child in node.children.iter().map(|child| nodes.get(child)) {
+        let start = child.span.start().into();
+        if child.is(Nodes::Md_Text) {
+            parts.push((start, input[child.span].to_string()));
+        } else if child.is(Nodes::Md_SoftBreak) || child.is(Nodes::Md_HardBreak) {
+            parts.push((start, " ".into()));
+        } else if child.is(Nodes::Md_WikiLink) {
+            let part = |name| {
+                let mut children = Children::new(child.children.iter().copied(), nodes);
+                children.find_node(name).map(|(_, part)| &input[part.span])
+            };
+            // Snippets are shown to players, secret links are left out.
+            if part(Nodes::Md_WikiSecret).is_some() {
+                continue;
+            }
+            let text = match (part(Nodes::Md_WikiLabel), part(Nodes::Md_WikiKind)) {
+                (Some(label), _) => label.to_string(),
+                (None, Some(kind)) => format!("{}:{}", kind, part(Nodes::Md_WikiId).unwrap_or("")),
+                (None, None) => String::new(),
+            };
+            parts.push((start, text));
+        } else if child.is(Nodes::Md_Link) && is_secret_link(child, nodes, input) {
+            continue;
+        } else {
+            collect_text(child, nodes, input, parts);
+        }
+    }
+}
+
+fn is_secret_link(link: &Node, nodes: &Arena, input: &str) -> bool {
+    let mut children = Children::new(link.children.iter().copied(), nodes);
+    children
+        .find_node(Nodes::Md_LinkUrl)
+        .map_or(false, |(_, url)| input[url.span].starts_with("secret:"))
+}
+
+fn find_mentions_in_md<'a>(
+    scope: &mut Scope<'a>,
+    node: &'a Node,
+    block: Option<&'a Node>,
+    mentions: &mut Vec<(NodeId, Mention)>,
 ) {
-    let children = node.children.iter().copied().collect::<Vec<NodeId>>();
-    for child_id in children {
+    let nodes = scope.nodes;
+    let input = scope.input;
+    for child_id in node.children.iter().copied() {
         let child = nodes.get(child_id);
+        let block = if is_block(child) { Some(child) } else { block };
+        if is_heading(child) {
+            scope.heading(child);
+        }
+
         if child.is(Nodes::Md_Link) {
             let mut children = Children::new(child.children.iter().copied(), nodes);
             if let Some((_, url)) = children.find_node(Nodes::Md_LinkUrl) {
                 let text = &input[url.span];
-                //TODO Parse :
-                let link_regex = Regex::new("([a-z_A-Z0-9]+):([0-9A-Fa-f]{8})").expect("Regex");
-                if let Some(cap) = link_regex.captures(text) {
+                if let Some(cap) = LINK_REGEX.captures(text.trim_start_matches("secret:")) {
                     let kind = cap.get(1).expect("G1").as_str();
                     let id = cap.get(2).expect("G2").as_str();
-                    mentions.push(Mention::new(orig_kind, orig_id, kind, id));
+                    let section = cap.get(3).map(|section| section.as_str());
+                    let mention = scope
+                        .mention(kind, id, block)
+                        .with_section(section)
+                        .with_secret(text.starts_with("secret:"));
+                    mentions.push((child_id, mention));
                 }
             }
+        } else if child.is(Nodes::Md_WikiLink) {
+            let part = |name| {
+                let mut children = Children::new(child.children.iter().copied(), nodes);
+                children.find_node(name).map(|(_, part)| &input[part.span])
+            };
+            if let (Some(kind), Some(id)) = (part(Nodes::Md_WikiKind), part(Nodes::Md_WikiId)) {
+                let secret = part(Nodes::Md_WikiSecret).is_some();
+                let mention = scope.mention(kind, id, block).with_secret(secret);
+                mentions.push((child_id, mention));
+            }
         }
-        find_mentions_in_md(_db, child, nodes, input, orig_kind, orig_id, mentions);
+        find_mentions_in_md(scope, child, block, mentions);
     }
 }
 
 fn find_mentions(
     _db: &dyn Analyzer,
     article_item: ArticleItem,
-    nodes: &mut Arena,
+    nodes: &Arena,
     input: &str,
-    mentions: &mut Vec<Mention>,
+    mentions: &mut Vec<(NodeId, Mention)>,
 ) -> Option<()> {
     let orig_kind = article_item.identifier(nodes, input).unwrap_or("???");
     let orig_id = article_item.item_id(nodes, input).unwrap_or("???");
+    let mut scope = Scope {
+        nodes,
+        input,
+        orig_kind,
+        orig_id,
+        anchor: None,
+        slugs: Slugs::default(),
+        secret: false,
+    };
 
-    let body = article_item.body?;
-    let body = nodes
-        .get(body)
-        .children
-        .iter()
-        .copied()
-        .collect::<Vec<NodeId>>();
+    find_mentions_in_body(&mut scope, article_item.body?, mentions);
+    Some(())
+}
 
-    for body_id in body {
+fn find_mentions_in_body<'a>(
+    scope: &mut Scope<'a>,
+    body: NodeId,
+    mentions: &mut Vec<(NodeId, Mention)>,
+) {
+    let nodes = scope.nodes;
+    let input = scope.input;
+    for body_id in nodes.get(body).children.iter().copied() {
         let body = nodes.get(body_id);
         if body.is(Nodes::ArticleItem) {
             let article_item =
                 ArticleItem::from_syntax(body_id, nodes).expect("body is ArticleItem");
             let kind = article_item.identifier(nodes, input).unwrap_or("???");
             let id = article_item.item_id(nodes, input).unwrap_or("???");
-            mentions.push(Mention::new(orig_kind, orig_id, kind, id));
+            mentions.push((body_id, scope.mention(kind, id, None)));
         } else if body.is(Nodes::ArticleRef) {
             let article_item = ArticleRef::from_syntax(body_id, nodes).expect("body is ArticleRef");
             let kind = article_item.identifier(nodes, input).unwrap_or("???");
             let id = article_item.item_id(nodes, input).unwrap_or("???");
-            mentions.push(Mention::new(orig_kind, orig_id, kind, id));
+            let section = article_item.section(nodes, input);
+            let mention = scope.mention(kind, id, None).with_section(section);
+            mentions.push((body_id, mention));
         } else if body.is(Nodes::Markdown) {
-            find_mentions_in_md(_db, body, nodes, input, orig_kind, orig_id, mentions);
+            // A single markdown block is merged with its `Markdown` node.
+            if is_heading(body) {
+                scope.heading(body);
+            }
+            let block = if is_block(body) { Some(body) } else { None };
+            find_mentions_in_md(scope, body, block, mentions);
+        } else if body.is(Nodes::Secret) {
+            let mut children = Children::new(body.children.iter().copied(), nodes);
+            if let Some((inner, _)) = children.find_node(Nodes::ArticleBody) {
+                // Headings inside the block mustn't leak into the mentions after it.
+                let outer = (scope.secret, scope.anchor.clone());
+                scope.secret = true;
+                find_mentions_in_body(scope, inner, mentions);
+                let (secret, anchor) = outer;
+                scope.secret = secret;
+                scope.anchor = anchor;
+            }
         }
     }
-
-    Some(())
 }
 
 #[cfg(test)]
@@ -107,11 +293,15 @@ mod tests {
 
     impl fmt::Display for Mention {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(
-                f,
-                "{}:{} in {}:{}",
-                self.kind, self.id, self.orig_kind, self.orig_id
-            )
+            write!(f, "{}:{}", self.kind, self.id)?;
+            if let Some(section) = &self.section {
+                write!(f, "#{}", section)?;
+            }
+            write!(f, " in {}:{}", self.orig_kind, self.orig_id)?;
+            if self.secret {
+                write!(f, " (secret)")?;
+            }
+            Ok(())
         }
     }
 
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+pub fn slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_')
+            && !slug.is_empty()
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        slug.push_str("section");
+    }
+    slug
+}
+
+#[derive(Debug, Default)]
+pub struct Slugs {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugs {
+    // Repeated headings get numbered slugs (`combat`, `combat-1`, ...) so every anchor is unique.
+    pub fn add(&mut self, text: &str) -> String {
+        let base = slug(text);
+        let count = self.seen.entry(base.clone()).or_insert(0);
+        let slug = match *count {
+            0 => base,
+            n => format!("{}-{}", base, n),
+        };
+        *count += 1;
+        slug
+    }
+}
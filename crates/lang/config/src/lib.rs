@@ -0,0 +1,266 @@
+use anyhow::{anyhow, Context, Result};
+use neu_db::{LintLevel, Lints};
+use neu_render::Audience;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const CONFIG_FILE: &str = "neu.toml";
+pub const DEFAULT_DIST: &str = ".neu";
+pub const TEMPLATES_DIR: &str = "templates";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    // Globs relative to the workspace root, the extension decides between articles and modules.
+    pub sources: Vec<String>,
+    pub exclude: Vec<String>,
+    pub dist: PathBuf,
+    pub templates: PathBuf,
+    pub audience: Audience,
+    pub address: String,
+    pub port: u16,
+    pub debounce: Duration,
+    pub lints: Lints,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sources: vec!["**/*.md".into(), "**/*.neu".into()],
+            exclude: vec![],
+            dist: DEFAULT_DIST.into(),
+            templates: TEMPLATES_DIR.into(),
+            audience: Audience::default(),
+            address: "127.0.0.1".into(),
+            port: 3000,
+            debounce: Duration::from_secs(2),
+            lints: Lints::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    sources: Option<Vec<String>>,
+    exclude: Vec<String>,
+    dist: Option<PathBuf>,
+    templates: Option<PathBuf>,
+    audience: Option<String>,
+    server: ServerSection,
+    watch: WatchSection,
+    lints: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerSection {
+    address: Option<String>,
+    port: Option<u16>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WatchSection {
+    // In milliseconds.
+    debounce: Option<u64>,
+}
+
+impl Config {
+    // `neu.toml` is optional, anything it leaves out keeps the default.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let input = std::fs::read_to_string(&path)?;
+        Self::parse(&input).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn parse(input: &str) -> Result<Self> {
+        let file: ConfigFile = toml::from_str(input)?;
+        let mut config = Self::default();
+
+        if let Some(sources) = file.sources {
+            config.sources = sources;
+        }
+        config.exclude = file.exclude;
+        for pattern in config.sources.iter().chain(&config.exclude) {
+            glob::Pattern::new(pattern)
+                .map_err(|e| anyhow!("Invalid pattern `{}`, {}", pattern, e))?;
+        }
+        if let Some(dist) = file.dist {
+            config.dist = dist;
+        }
+        if let Some(templates) = file.templates {
+            config.templates = templates;
+        }
+        if let Some(audience) = file.audience {
+            config.audience = audience.parse().map_err(anyhow::Error::msg)?;
+        }
+        if let Some(address) = file.server.address {
+            config.address = address;
+        }
+        if let Some(port) = file.server.port {
+            config.port = port;
+        }
+        if let Some(debounce) = file.watch.debounce {
+            config.debounce = Duration::from_millis(debounce);
+        }
+        for (lint, level) in file.lints {
+            let level = level.parse().map_err(anyhow::Error::msg)?;
+            config.lints.set(&lint, level).map_err(anyhow::Error::msg)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn is_source(&self, root: &Path, path: &Path) -> bool {
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+        let matches = |pattern: &String| {
+            glob::Pattern::new(pattern).map_or(false, |pattern| pattern.matches_path(relative))
+        };
+        self.sources.iter().any(matches) && !self.exclude.iter().any(matches)
+    }
+
+    pub fn is_template(&self, root: &Path, path: &Path) -> bool {
+        path.starts_with(root.join(&self.templates))
+    }
+
+    pub fn server_addr(&self) -> Result<SocketAddr> {
+        (self.address.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("Couldn't resolve {}:{}", self.address, self.port))
+    }
+}
+
+// Flags given on the command line win over `neu.toml`.
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    pub dist: Option<PathBuf>,
+    pub audience: Option<Audience>,
+    pub lint_orphans: bool,
+}
+
+impl Overrides {
+    pub fn load(&self, root: &Path) -> Result<Config> {
+        let mut config = Config::load(root)?;
+        if let Some(dist) = &self.dist {
+            config.dist = dist.clone();
+        }
+        if let Some(audience) = self.audience {
+            config.audience = audience;
+        }
+        if self.lint_orphans && config.lints.orphans == LintLevel::Allow {
+            config.lints.orphans = LintLevel::Warn;
+        }
+        Ok(config)
+    }
+}
+
+// The workspace root holds either `neu.toml` or the dist directory.
+pub fn find_workspace(start: Option<PathBuf>, dist: Option<&Path>) -> Result<PathBuf> {
+    let dist = dist.unwrap_or_else(|| Path::new(DEFAULT_DIST));
+    let mut path = match start {
+        Some(s) => s,
+        None => std::env::current_dir()?,
+    };
+
+    while !path.join(CONFIG_FILE).is_file() && !path.join(dist).exists() {
+        path = path
+            .parent()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Couldn't find {} or {} directory",
+                    CONFIG_FILE,
+                    dist.display()
+                )
+            })?
+            .into();
+    }
+
+    path = path.canonicalize()?;
+
+    log::debug!("Found workspace at: {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn parse_config() -> Result<()> {
+        let config = Config::parse(
+            r#"
+sources = ["campaign/**/*.md", "lib/*.neu"]
+exclude = ["campaign/drafts/**"]
+dist = "build"
+audience = "player"
+
+[server]
+address = "0.0.0.0"
+port = 8080
+
+[watch]
+debounce = 500
+
+[lints]
+orphans = "warn"
+links = "deny"
+"#,
+        )?;
+        assert_eq!(config.dist, PathBuf::from("build"));
+        assert_eq!(config.templates, PathBuf::from(TEMPLATES_DIR));
+        assert_eq!(config.audience, Audience::Player);
+        assert_eq!(config.server_addr()?, "0.0.0.0:8080".parse::<SocketAddr>()?);
+        assert_eq!(config.debounce, Duration::from_millis(500));
+        assert_eq!(config.lints.orphans, LintLevel::Warn);
+        assert_eq!(config.lints.links, LintLevel::Deny);
+        assert_eq!(config.lints.dates, LintLevel::Warn);
+
+        let root = Path::new("/world");
+        assert!(config.is_source(root, Path::new("/world/campaign/npc/borys.md")));
+        assert!(config.is_source(root, Path::new("/world/lib/calendar.neu")));
+        assert!(!config.is_source(root, Path::new("/world/campaign/drafts/x.md")));
+        assert!(!config.is_source(root, Path::new("/world/notes.md")));
+
+        assert_eq!(Config::parse("")?, Config::default());
+        assert!(Config::parse("[lints]\ntypos = \"warn\"").is_err());
+        assert!(Config::parse("[lints]\nlinks = \"loud\"").is_err());
+        assert!(Config::parse("port = 3000").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_with_config_file() -> Result<()> {
+        let temp = assert_fs::TempDir::new()?;
+        temp.child(CONFIG_FILE).write_str("dist = \"build\"\n")?;
+        let child = temp.child("npc");
+        child.create_dir_all()?;
+
+        let root = find_workspace(Some(child.path().into()), None)?;
+        assert_eq!(root, temp.path().canonicalize()?);
+        assert_eq!(Config::load(&root)?.dist, PathBuf::from("build"));
+
+        let overrides = Overrides {
+            dist: Some("public".into()),
+            audience: Some(Audience::Player),
+            lint_orphans: true,
+        };
+        let config = overrides.load(&root)?;
+        assert_eq!(config.dist, PathBuf::from("public"));
+        assert_eq!(config.audience, Audience::Player);
+        assert_eq!(config.lints.orphans, LintLevel::Warn);
+
+        temp.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use derive_more::Display;
+
+#[derive(Debug, Display)]
+pub enum Error {
+    #[display(fmt = "Couldn't find article {}:{}", kind, id)]
+    ArticleNotFound { kind: String, id: String },
+
+    #[display(fmt = "Duplicate article {}:{}, also declared in {}", kind, id, paths)]
+    DuplicateArticle {
+        kind: String,
+        id: String,
+        paths: String,
+    },
+
+    #[display(fmt = "Article {}:{} is not referenced anywhere", kind, id)]
+    OrphanArticle { kind: String, id: String },
+
+    #[display(fmt = "Date of {}:{} doesn't fit the calendar, {}", kind, id, reason)]
+    DateOutOfCalendar {
+        kind: String,
+        id: String,
+        reason: String,
+    },
+
+    #[display(
+        fmt = "Public content mentions secret article {}:{}, players won't see this link",
+        kind,
+        id
+    )]
+    SecretReference { kind: String, id: String },
+}
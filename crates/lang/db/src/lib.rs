@@ -1,12 +1,32 @@
+use crate::error::Error;
+use itertools::Itertools;
 use neu_canceled::Canceled;
 use neu_diagnostics::Diagnostic;
+use neu_eval::Calendar;
 use neu_parser::NodeId;
 use neu_render::db::Renderer;
+use neu_syntax::ast::ArticleItem;
 use neu_syntax::db::{FileId, Parser};
+use neu_syntax::Nodes;
+use std::collections::{BTreeMap, HashSet};
+
+mod error;
+mod lints;
+
+pub use lints::{LintLevel, Lints};
 
 #[salsa::query_group(DiagnosticianDatabase)]
 pub trait Diagnostician: salsa::Database + Parser + Renderer {
+    #[salsa::input]
+    fn lints(&self) -> Lints;
+
     fn all_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
+    fn denied_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
+    fn link_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
+    fn duplicate_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
+    fn orphan_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
+    fn date_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
+    fn secret_diagnostics(&self) -> Vec<(FileId, NodeId, Diagnostic)>;
 }
 
 fn all_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
@@ -43,15 +63,177 @@ fn all_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)>
         });
 
     diagnostics.extend(md);
+    diagnostics.extend(lint_diagnostics(db, |level| level != LintLevel::Allow));
+    diagnostics
+}
+
+// Denied lints fail the build.
+fn denied_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
+    Canceled::cancel_if(db.salsa_runtime());
+    lint_diagnostics(db, |level| level == LintLevel::Deny)
+}
+
+fn lint_diagnostics(
+    db: &dyn Diagnostician,
+    enabled: impl Fn(LintLevel) -> bool,
+) -> Vec<(FileId, NodeId, Diagnostic)> {
+    let lints = db.lints();
+    let mut diagnostics = vec![];
+    if enabled(lints.links) {
+        diagnostics.extend(db.link_diagnostics());
+    }
+    if enabled(lints.duplicates) {
+        diagnostics.extend(db.duplicate_diagnostics());
+    }
+    if enabled(lints.dates) {
+        diagnostics.extend(db.date_diagnostics());
+    }
+    if enabled(lints.secrets) {
+        diagnostics.extend(db.secret_diagnostics());
+    }
+    if enabled(lints.orphans) {
+        diagnostics.extend(db.orphan_diagnostics());
+    }
+    diagnostics
+}
+
+fn item_node(article_item: &ArticleItem) -> NodeId {
+    article_item.item_id.unwrap_or(article_item.id)
+}
+
+fn link_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    db.all_mention_nodes()
+        .into_iter()
+        .filter(|(path, node, mention)| {
+            // Nested articles always exist.
+            let parsed = db.parse_syntax(*path);
+            let nested = parsed.arena.get(*node).is(Nodes::ArticleItem);
+            let (kind, id) = (mention.kind.clone(), mention.id.clone());
+            !nested && db.find_md(kind, id).is_none()
+        })
+        .map(|(path, node, mention)| {
+            let error = Error::ArticleNotFound {
+                kind: mention.kind,
+                id: mention.id,
+            };
+            (path, node, error.to_string())
+        })
+        .collect()
+}
+
+fn duplicate_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let mut declarations = BTreeMap::<_, Vec<_>>::new();
+    for (kind, id, path, article_item) in db.parse_all_mds() {
+        declarations
+            .entry((kind, id))
+            .or_default()
+            .push((path, item_node(&article_item)));
+    }
+
+    let mut diagnostics = vec![];
+    for ((kind, id), declared) in declarations {
+        if declared.len() < 2 {
+            continue;
+        }
+        for (idx, (path, node)) in declared.iter().enumerate() {
+            let paths = declared
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .map(|(_, (other, _))| db.lookup_file_id(*other).0)
+                .join(", ");
+            let error = Error::DuplicateArticle {
+                kind: kind.clone(),
+                id: id.clone(),
+                paths,
+            };
+            diagnostics.push((*path, *node, error.to_string()));
+        }
+    }
     diagnostics
 }
 
+fn orphan_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let mentioned = db
+        .all_mentions()
+        .into_iter()
+        .map(|mention| (mention.kind, mention.id))
+        .collect::<HashSet<_>>();
+
+    db.parse_all_mds()
+        .into_iter()
+        .filter(|(kind, id, _, _)| !mentioned.contains(&(kind.clone(), id.clone())))
+        .map(|(kind, id, path, article_item)| {
+            let error = Error::OrphanArticle { kind, id };
+            (path, item_node(&article_item), error.to_string())
+        })
+        .collect()
+}
+
+fn date_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let mut diagnostics = vec![];
+    if let Some(file) = db.calendar_file() {
+        let root = db.parse_syntax(file).root;
+        if let Some(value) = db.eval(file, root).value.clone() {
+            if let Err(error) = Calendar::from_value(&value) {
+                diagnostics.push((file, root, error));
+            }
+        }
+    }
+
+    let calendar = db.calendar();
+    for entry in db.timeline().iter() {
+        if let Err(reason) = calendar.check(&entry.date) {
+            let error = Error::DateOutOfCalendar {
+                kind: entry.kind.clone(),
+                id: entry.id.clone(),
+                reason,
+            };
+            diagnostics.push((entry.file, entry.node, error.to_string()));
+        }
+    }
+    diagnostics
+}
+
+fn secret_diagnostics(db: &dyn Diagnostician) -> Vec<(FileId, NodeId, Diagnostic)> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let secret = db.secret_articles();
+    db.all_mention_nodes()
+        .into_iter()
+        .filter(|(path, node, mention)| {
+            // Secret nested items are declared where they are written, not referenced.
+            let parsed = db.parse_syntax(*path);
+            let nested = parsed.arena.get(*node).is(Nodes::ArticleItem);
+            let orig = (mention.orig_kind.clone(), mention.orig_id.clone());
+            let target = (mention.kind.clone(), mention.id.clone());
+            let public = !mention.secret && !secret.contains(&orig);
+            !nested && public && secret.contains(&target)
+        })
+        .map(|(path, node, mention)| {
+            let error = Error::SecretReference {
+                kind: mention.kind,
+                id: mention.id,
+            };
+            (path, node, error.to_string())
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use itertools::Itertools;
-    use neu_syntax::db::FileKind;
-    use std::sync::Arc;
+    use neu_render::{Audience, HtmlPolicy};
+    use neu_syntax::db::load_files;
 
     #[salsa::database(
         neu_render::db::RendererDatabase,
@@ -70,12 +252,7 @@ mod tests {
     #[test]
     fn neu_errors_tests() {
         test_runner::test_snapshots("neu", "errors", |input| {
-            let mut db = TestDb::default();
-            //let path: FileId = ("test.neu".into(), FileKind::Neu);
-            let path = db.file_id(("test.neu".into(), FileKind::Neu));
-            db.set_all_mds(Default::default());
-            db.set_all_neu(Arc::new(Some(path.clone()).into_iter().collect()));
-            db.set_input(path, Arc::new(input.into()));
+            let db = test_db(&[("test.neu", input)], Lints::default());
 
             let diagnostics = db.all_diagnostics();
             if diagnostics.is_empty() {
@@ -95,12 +272,7 @@ mod tests {
     #[test]
     fn md_errors_tests() {
         test_runner::test_snapshots("md", "errors", |input| {
-            let mut db = TestDb::default();
-            //let path: FileId = ("test.md".into(), FileKind::Md);
-            let path = db.file_id(("test.md".into(), FileKind::Md));
-            db.set_all_neu(Default::default());
-            db.set_all_mds(Arc::new(Some(path.clone()).into_iter().collect()));
-            db.set_input(path, Arc::new(input.into()));
+            let db = test_db(&[("test.md", input)], Lints::default());
 
             let diagnostics = db.all_diagnostics();
             if diagnostics.is_empty() {
@@ -116,4 +288,134 @@ mod tests {
         })
         .unwrap();
     }
+
+    fn test_db(files: &[(&str, &str)], lints: Lints) -> TestDb {
+        let mut db = TestDb::default();
+        load_files(&mut db, files);
+        db.set_html_policy(HtmlPolicy::Allow);
+        db.set_audience(Audience::Gm);
+        db.set_lints(lints);
+        db
+    }
+
+    fn format(db: &TestDb, diagnostics: Vec<(FileId, NodeId, Diagnostic)>) -> Vec<String> {
+        diagnostics
+            .into_iter()
+            .map(|(path, _, diagnostic)| format!("{} | {}", db.lookup_file_id(path).0, diagnostic))
+            .sorted()
+            .collect()
+    }
+
+    fn diagnostics(files: &[(&str, &str)], lint_orphans: bool) -> Vec<String> {
+        let mut lints = Lints::default();
+        if lint_orphans {
+            lints.orphans = LintLevel::Warn;
+        }
+        let db = test_db(files, lints);
+        format(&db, db.all_diagnostics())
+    }
+
+    const SESJA: &str = r#"+++ sesja:a88907fd +++
+title = "Sesja"
++++
+
+[+ npc:dddddddd +]
+
+[Borys](npc:0123abcd) and [Ghost](npc:ffffffff)
+
+[[npc:0123abcd]] and [[npc:eeeeeeee]]
+"#;
+
+    const NPC: &str = r#"+++ npc:0123abcd +++
+title = "Borys"
++++
+"#;
+
+    #[test]
+    fn unresolved_links() {
+        assert_eq!(
+            diagnostics(&[("a.md", SESJA), ("b.md", NPC)], false),
+            vec![
+                "a.md | Couldn't find article npc:dddddddd",
+                "a.md | Couldn't find article npc:eeeeeeee",
+                "a.md | Couldn't find article npc:ffffffff",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_articles() {
+        assert_eq!(
+            diagnostics(&[("a.md", NPC), ("b.md", NPC)], false),
+            vec![
+                "a.md | Duplicate article npc:0123abcd, also declared in b.md",
+                "b.md | Duplicate article npc:0123abcd, also declared in a.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn orphan_articles_are_opt_in() {
+        let sesja = "+++ sesja:a88907fd +++\ntitle = \"Sesja\"\n+++\n\n[[npc:0123abcd]]\n";
+        let files = [("a.md", sesja), ("b.md", NPC)];
+        assert!(diagnostics(&files, false).is_empty());
+        assert_eq!(
+            diagnostics(&files, true),
+            vec!["a.md | Article sesja:a88907fd is not referenced anywhere"]
+        );
+    }
+
+    #[test]
+    fn lint_levels() {
+        let mut lints = Lints::default();
+        lints.set("links", LintLevel::Allow).unwrap();
+        lints.set("duplicates", LintLevel::Deny).unwrap();
+        let db = test_db(&[("a.md", SESJA), ("b.md", NPC), ("c.md", NPC)], lints);
+        assert_eq!(
+            format(&db, db.all_diagnostics()),
+            vec![
+                "b.md | Duplicate article npc:0123abcd, also declared in c.md",
+                "c.md | Duplicate article npc:0123abcd, also declared in b.md",
+            ]
+        );
+        assert_eq!(format(&db, db.denied_diagnostics()).len(), 2);
+
+        let mut lints = Lints::default();
+        lints.set("links", LintLevel::Deny).unwrap();
+        let db = test_db(&[("a.md", SESJA), ("b.md", NPC)], lints);
+        assert_eq!(
+            format(&db, db.denied_diagnostics()),
+            vec![
+                "a.md | Couldn't find article npc:dddddddd",
+                "a.md | Couldn't find article npc:eeeeeeee",
+                "a.md | Couldn't find article npc:ffffffff",
+            ]
+        );
+
+        assert!(Lints::default().set("typos", LintLevel::Warn).is_err());
+        assert_eq!("deny".parse(), Ok(LintLevel::Deny));
+    }
+
+    #[test]
+    fn public_mentions_of_secret_articles() {
+        let npc = "+++ npc:0123abcd +++\ntitle = \"Borys\"\nsecret = true\n+++\n";
+        let sesja = r#"+++ sesja:a88907fd +++
+title = "Sesja"
++++
+
+[[npc:0123abcd]], [[secret:npc:0123abcd]] and [Borys](secret:npc:0123abcd)
+
+++ secret ++
+
+[[npc:0123abcd]]
+
+++ end ++
+"#;
+        assert_eq!(
+            diagnostics(&[("a.md", sesja), ("b.md", npc)], false),
+            vec![
+                "a.md | Public content mentions secret article npc:0123abcd, players won't see this link"
+            ]
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl FromStr for LintLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => Err(format!(
+                "Unknown lint level `{}`, expected `allow`, `warn` or `deny`",
+                s
+            )),
+        }
+    }
+}
+
+// Syntax and evaluation errors are always reported, only these checks can be tuned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lints {
+    pub links: LintLevel,
+    pub duplicates: LintLevel,
+    pub orphans: LintLevel,
+    pub dates: LintLevel,
+    pub secrets: LintLevel,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Self {
+            links: LintLevel::Warn,
+            duplicates: LintLevel::Warn,
+            orphans: LintLevel::Allow,
+            dates: LintLevel::Warn,
+            secrets: LintLevel::Warn,
+        }
+    }
+}
+
+impl Lints {
+    pub fn set(&mut self, lint: &str, level: LintLevel) -> Result<(), String> {
+        let slot = match lint {
+            "links" => &mut self.links,
+            "duplicates" => &mut self.duplicates,
+            "orphans" => &mut self.orphans,
+            "dates" => &mut self.dates,
+            "secrets" => &mut self.secrets,
+            _ => {
+                return Err(format!(
+                    "Unknown lint `{}`, expected one of `links`, `duplicates`, `orphans`, `dates` or `secrets`",
+                    lint
+                ))
+            }
+        };
+        *slot = level;
+        Ok(())
+    }
+}
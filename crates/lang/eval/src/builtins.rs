@@ -0,0 +1,224 @@
+use crate::error::Error;
+use crate::{format, Eval, Rng, Table, Value};
+use neu_parser::{Children, Node};
+use neu_syntax::Nodes;
+use std::ops::RangeInclusive;
+
+impl<'a> Eval<'a> {
+    pub(crate) fn eval_call(&mut self, node: &Node) -> Option<Value> {
+        let mut children = Children::new(node.children.iter().copied(), self.arena);
+        let (name_id, name) = children.find_node(Nodes::Identifier)?;
+        let name = &self.input[name.span];
+
+        if name == "has" {
+            let arg_ids = std::iter::from_fn(|| children.find_node(Nodes::Value))
+                .map(|(arg_id, _)| arg_id)
+                .collect::<Vec<_>>();
+            if let Err(error) = arity(name, arg_ids.len(), 1..=1) {
+                self.report(name_id, error);
+                return None;
+            }
+            self.optional = true;
+            let value = self.eager_eval(arg_ids[0], false)?;
+            return Some(Value::Boolean(!value.is_null()));
+        }
+
+        let mut args = vec![];
+        while let Some((arg_id, _)) = children.find_node(Nodes::Value) {
+            args.push(self.eager_eval(arg_id, true)?);
+        }
+
+        let result = if name == "roll_on" {
+            // Evaluation is memoized, so the roll is seeded from the file and the call site.
+            arity(name, args.len(), 1..=1).and_then(|_| {
+                let table = table_arg(name, &args, 0)?;
+                let (path, _) = self.db.lookup_file_id(self.file);
+                Ok(table.roll(&mut Rng::from_key((path, name_id))))
+            })
+        } else {
+            call_builtin(name, args)
+        };
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.report(name_id, error);
+                None
+            }
+        }
+    }
+}
+
+fn call_builtin(name: &str, args: Vec<Value>) -> Result<Value, Error> {
+    match name {
+        "upper" => {
+            arity(name, args.len(), 1..=1)?;
+            Ok(Value::String(string_arg(name, &args, 0)?.to_uppercase()))
+        }
+        "lower" => {
+            arity(name, args.len(), 1..=1)?;
+            Ok(Value::String(string_arg(name, &args, 0)?.to_lowercase()))
+        }
+        "trim" => {
+            arity(name, args.len(), 1..=1)?;
+            Ok(Value::String(string_arg(name, &args, 0)?.trim().into()))
+        }
+        "split" => {
+            arity(name, args.len(), 2..=2)?;
+            let s = string_arg(name, &args, 0)?;
+            let separator = string_arg(name, &args, 1)?;
+            Ok(Value::Array(
+                s.split(separator)
+                    .map(|part| Value::String(part.into()))
+                    .collect(),
+            ))
+        }
+        "replace" => {
+            arity(name, args.len(), 3..=3)?;
+            let s = string_arg(name, &args, 0)?;
+            let from = string_arg(name, &args, 1)?;
+            let to = string_arg(name, &args, 2)?;
+            Ok(Value::String(s.replace(from, to)))
+        }
+        "starts_with" => {
+            arity(name, args.len(), 2..=2)?;
+            let s = string_arg(name, &args, 0)?;
+            let prefix = string_arg(name, &args, 1)?;
+            Ok(Value::Boolean(s.starts_with(prefix)))
+        }
+        "ends_with" => {
+            arity(name, args.len(), 2..=2)?;
+            let s = string_arg(name, &args, 0)?;
+            let suffix = string_arg(name, &args, 1)?;
+            Ok(Value::Boolean(s.ends_with(suffix)))
+        }
+        "contains" => {
+            arity(name, args.len(), 2..=2)?;
+            let s = string_arg(name, &args, 0)?;
+            let needle = string_arg(name, &args, 1)?;
+            Ok(Value::Boolean(s.contains(needle)))
+        }
+        "len" => {
+            arity(name, args.len(), 1..=1)?;
+            let len = match &args[0] {
+                Value::String(s) => s.chars().count(),
+                Value::Array(a) => a.len(),
+                Value::Tuple(t) => t.len(),
+                Value::Struct(s) => s.len(),
+                other => return Err(invalid(name, format!("{} has no length", other.kind()))),
+            };
+            Ok(Value::Number(len as i64))
+        }
+        "substr" => {
+            arity(name, args.len(), 2..=3)?;
+            let s = string_arg(name, &args, 0)?;
+            let start = index_arg(name, &args, 1)?;
+            let len = match args.get(2) {
+                Some(_) => index_arg(name, &args, 2)?,
+                None => s.chars().count(),
+            };
+            Ok(Value::String(s.chars().skip(start).take(len).collect()))
+        }
+        "to_string" => {
+            arity(name, args.len(), 1..=1)?;
+            Ok(Value::String(format::interpolate(&args[0])))
+        }
+        "parse_number" => {
+            arity(name, args.len(), 1..=1)?;
+            let s = string_arg(name, &args, 0)?;
+            s.trim()
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| invalid(name, format!("`{}` is not a number", s)))
+        }
+        "format" => {
+            arity(name, args.len(), 1..=usize::MAX)?;
+            let template = string_arg(name, &args, 0)?;
+            format::format(template, &args[1..])
+                .map(Value::String)
+                .map_err(Error::InvalidFormat)
+        }
+        "table" => {
+            arity(name, args.len(), 1..=1)?;
+            Table::from_value(&args[0])
+                .map(Value::Table)
+                .map_err(|reason| invalid(name, reason))
+        }
+        "pick" => {
+            arity(name, args.len(), 2..=2)?;
+            let table = table_arg(name, &args, 0)?;
+            match &args[1] {
+                Value::Number(seed) => Ok(table.pick(*seed as u64)),
+                other => Err(invalid(
+                    name,
+                    format!("argument 2 should be a number, found {}", other.kind()),
+                )),
+            }
+        }
+        _ => Err(Error::UnknownFunction(name.into())),
+    }
+}
+
+fn invalid(name: &str, reason: String) -> Error {
+    Error::InvalidArguments {
+        name: name.into(),
+        reason,
+    }
+}
+
+fn arity(name: &str, found: usize, expected: RangeInclusive<usize>) -> Result<(), Error> {
+    if expected.contains(&found) {
+        return Ok(());
+    }
+    let expected = match (expected.start(), expected.end()) {
+        (start, end) if start == end => start.to_string(),
+        (start, &usize::MAX) => format!("at least {}", start),
+        (start, end) => format!("{} to {}", start, end),
+    };
+    Err(Error::WrongArgumentCount {
+        name: name.into(),
+        expected,
+        found,
+    })
+}
+
+fn string_arg<'v>(name: &str, args: &'v [Value], idx: usize) -> Result<&'v str, Error> {
+    match &args[idx] {
+        Value::String(s) => Ok(s),
+        other => Err(invalid(
+            name,
+            format!(
+                "argument {} should be a string, found {}",
+                idx + 1,
+                other.kind()
+            ),
+        )),
+    }
+}
+
+fn table_arg<'v>(name: &str, args: &'v [Value], idx: usize) -> Result<&'v Table, Error> {
+    match &args[idx] {
+        Value::Table(table) => Ok(table),
+        other => Err(invalid(
+            name,
+            format!(
+                "argument {} should be a table, found {}",
+                idx + 1,
+                other.kind()
+            ),
+        )),
+    }
+}
+
+fn index_arg(name: &str, args: &[Value], idx: usize) -> Result<usize, Error> {
+    match &args[idx] {
+        Value::Number(n) if *n >= 0 => Ok(*n as usize),
+        other => Err(invalid(
+            name,
+            format!(
+                "argument {} should be a non-negative number, found {}",
+                idx + 1,
+                other
+            ),
+        )),
+    }
+}
@@ -0,0 +1,205 @@
+use crate::error::Error;
+use crate::Value;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub time: Option<(u32, u32)>,
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (date, time) = match s.find('T') {
+            Some(idx) => (&s[..idx], Some(&s[idx + 1..])),
+            None => (s, None),
+        };
+        let (sign, date) = match date.chars().next() {
+            Some('-') => (-1, &date[1..]),
+            _ => (1, date),
+        };
+        let number = |part: Option<&str>| {
+            part.and_then(|part| part.parse::<u32>().ok())
+                .ok_or_else(|| format!("expected `year-month-day`, got `{}`", s))
+        };
+
+        let mut parts = date.splitn(3, '-');
+        let year = number(parts.next())?;
+        let month = number(parts.next())?;
+        let day = number(parts.next())?;
+        if month == 0 || day == 0 {
+            return Err("months and days start from 1".into());
+        }
+
+        let time = match time {
+            Some(time) => {
+                let invalid = || format!("`{}` is not a valid time, expected `hour:minute`", time);
+                let mut parts = time.splitn(2, ':');
+                let mut number = || {
+                    parts
+                        .next()
+                        .and_then(|part| part.parse::<u32>().ok())
+                        .ok_or_else(invalid)
+                };
+                let hour = number()?;
+                let minute = number()?;
+                if hour > 23 || minute > 59 {
+                    return Err(invalid());
+                }
+                Some((hour, minute))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            year: sign * i64::from(year),
+            month,
+            day,
+            time,
+        })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{:02}-{:02}", self.year, self.month, self.day)?;
+        if let Some((hour, minute)) = self.time {
+            write!(f, "T{:02}:{:02}", hour, minute)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Month {
+    pub name: String,
+    pub days: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Era {
+    pub name: String,
+    pub start: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+    pub months: Vec<Month>,
+    pub eras: Vec<Era>,
+}
+
+// Without a `calendar.neu` in the workspace dates follow the Gregorian months, leap days aside.
+impl Default for Calendar {
+    fn default() -> Self {
+        let months = [
+            ("January", 31),
+            ("February", 28),
+            ("March", 31),
+            ("April", 30),
+            ("May", 31),
+            ("June", 30),
+            ("July", 31),
+            ("August", 31),
+            ("September", 30),
+            ("October", 31),
+            ("November", 30),
+            ("December", 31),
+        ];
+        Self {
+            months: months
+                .iter()
+                .map(|(name, days)| Month {
+                    name: name.to_string(),
+                    days: *days,
+                })
+                .collect(),
+            eras: vec![],
+        }
+    }
+}
+
+impl Calendar {
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let invalid = |reason: &str| Error::InvalidCalendar(reason.into()).to_string();
+        let fields = match value {
+            Value::Struct(fields) => fields,
+            _ => return Err(invalid("expected a struct with `months`")),
+        };
+
+        let months = match fields.get("months") {
+            Some(Value::Array(months)) if !months.is_empty() => months
+                .iter()
+                .map(|month| match (field(month, "name"), field(month, "days")) {
+                    (Some(Value::String(name)), Some(Value::Number(days))) => {
+                        match u32::try_from(*days) {
+                            Ok(days) if days > 0 => Ok(Month {
+                                name: name.clone(),
+                                days,
+                            }),
+                            _ => Err(invalid(&format!("`{}` has {} days", name, days))),
+                        }
+                    }
+                    _ => Err(invalid("every month needs a `name` and positive `days`")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(invalid("`months` should be a non-empty array")),
+        };
+
+        let mut eras = match fields.get("eras") {
+            None => vec![],
+            Some(Value::Array(eras)) => eras
+                .iter()
+                .map(|era| match (field(era, "name"), field(era, "start")) {
+                    (Some(Value::String(name)), Some(Value::Number(start))) => Ok(Era {
+                        name: name.clone(),
+                        start: *start,
+                    }),
+                    _ => Err(invalid("every era needs a `name` and a `start` year")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(invalid("`eras` should be an array")),
+        };
+        eras.sort_by_key(|era| era.start);
+
+        Ok(Self { months, eras })
+    }
+
+    pub fn check(&self, date: &Date) -> Result<(), String> {
+        let month = self
+            .months
+            .get(date.month as usize - 1)
+            .ok_or_else(|| format!("the calendar has only {} months", self.months.len()))?;
+        if date.day > month.days {
+            return Err(format!("{} has only {} days", month.name, month.days));
+        }
+        Ok(())
+    }
+
+    pub fn format(&self, date: &Date) -> String {
+        let mut formatted = match self.months.get(date.month as usize - 1) {
+            Some(month) => format!("{} {} {}", date.day, month.name, date.year),
+            None => format!("{}-{:02}-{:02}", date.year, date.month, date.day),
+        };
+        if let Some((hour, minute)) = date.time {
+            formatted.push_str(&format!(", {:02}:{:02}", hour, minute));
+        }
+        formatted
+    }
+
+    pub fn era(&self, year: i64) -> Option<&Era> {
+        self.eras.iter().rev().find(|era| era.start <= year)
+    }
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    match value {
+        Value::Struct(fields) => fields.get(name),
+        _ => None,
+    }
+}
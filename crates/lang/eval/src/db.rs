@@ -1,16 +1,44 @@
 #![allow(dead_code)]
 use crate::result::EvalResult;
-use crate::Eval;
+use crate::{Calendar, Eval};
 use neu_canceled::Canceled;
 use neu_parser::{NodeId, ParseResult};
 use neu_syntax::ast::{ArticleItem, Ast};
 use neu_syntax::db::{FileId, Parser};
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::Path;
 use std::sync::Arc;
 
 #[salsa::query_group(EvaluatorDatabase)]
 pub trait Evaluator: salsa::Database + Parser {
     fn eval(&self, file: FileId, id: NodeId) -> Arc<EvalResult>;
     fn anchored(&self, file: FileId) -> Arc<ParseResult>;
+
+    fn calendar_file(&self) -> Option<FileId>;
+    fn calendar(&self) -> Arc<Calendar>;
+}
+
+// The workspace calendar is the `calendar.neu` closest to the root.
+fn calendar_file(db: &dyn Evaluator) -> Option<FileId> {
+    Canceled::cancel_if(db.salsa_runtime());
+    db.all_neu()
+        .iter()
+        .copied()
+        .map(|file| (db.lookup_file_id(file).0, file))
+        .filter(|(path, _)| Path::new(path).file_name() == Some(OsStr::new("calendar.neu")))
+        .min_by_key(|(path, _)| (Path::new(path).components().count(), path.clone()))
+        .map(|(_, file)| file)
+}
+
+fn calendar(db: &dyn Evaluator) -> Arc<Calendar> {
+    Canceled::cancel_if(db.salsa_runtime());
+    let calendar = db.calendar_file().and_then(|file| {
+        let parsed = db.parse_syntax(file);
+        let value = db.eval(file, parsed.root).value.clone()?;
+        Calendar::from_value(&value).ok()
+    });
+    Arc::new(calendar.unwrap_or_default())
 }
 
 fn anchored(db: &dyn Evaluator, file: FileId) -> Arc<ParseResult> {
@@ -34,7 +62,7 @@ fn eval(db: &dyn Evaluator, file: FileId, id: NodeId) -> Arc<EvalResult> {
     Canceled::cancel_if(db.salsa_runtime());
     let input = db.input(file);
     let parsed = db.anchored(file);
-    let mut eval = Eval::new(&parsed.arena, &input);
+    let mut eval = Eval::new(db, file, &parsed.arena, &input);
     let value = eval.eval(id).and_then(|val| eval.into_eager(val, true));
     let errors = eval.errors;
     Arc::new(EvalResult {
@@ -42,3 +70,22 @@ fn eval(db: &dyn Evaluator, file: FileId, id: NodeId) -> Arc<EvalResult> {
         errors
     })
 }
+
+pub(crate) fn imports_reach(db: &dyn Evaluator, from: FileId, to: FileId) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(file) = stack.pop() {
+        if file == to {
+            return true;
+        }
+        if !visited.insert(file) {
+            continue;
+        }
+        stack.extend(
+            db.file_imports(file)
+                .iter()
+                .filter_map(|(_, target)| *target),
+        );
+    }
+    false
+}
@@ -11,6 +11,83 @@ pub enum Error {
 
     #[display(fmt = "Field not found")]
     FieldNotFound,
+
+    #[display(fmt = "Couldn't find imported file `{}`", _0)]
+    ImportNotFound(String),
+
+    #[display(fmt = "Import cycle detected")]
+    ImportCycle,
+
+    #[display(fmt = "Operator `{}` cannot be applied to {} and {}", op, left, right)]
+    InvalidOperands {
+        op: String,
+        left: &'static str,
+        right: &'static str,
+    },
+
+    #[display(fmt = "Unknown function `{}`", _0)]
+    UnknownFunction(String),
+
+    #[display(
+        fmt = "Function `{}` expects {} arguments but got {}",
+        name,
+        expected,
+        found
+    )]
+    WrongArgumentCount {
+        name: String,
+        expected: String,
+        found: usize,
+    },
+
+    #[display(fmt = "Invalid arguments for `{}`: {}", name, reason)]
+    InvalidArguments { name: String, reason: String },
+
+    #[display(fmt = "Invalid format string: {}", _0)]
+    InvalidFormat(String),
+
+    #[display(fmt = "Unknown variant `{}`", _0)]
+    UnknownVariant(String),
+
+    #[display(
+        fmt = "Variant `{}` expects {} fields but got {}",
+        name,
+        expected,
+        found
+    )]
+    WrongFieldCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[display(fmt = "Non-exhaustive match, missing {}", _0)]
+    NonExhaustiveMatch(String),
+
+    #[display(fmt = "Unreachable match arm")]
+    UnreachableArm,
+
+    #[display(fmt = "No match arm matches {}", _0)]
+    NoMatchingArm(String),
+
+    #[display(fmt = "Expression is not a tuple")]
+    ValueNotTuple,
+
+    #[display(
+        fmt = "Tuple index {} is out of range for a tuple of {} elements",
+        index,
+        len
+    )]
+    TupleIndexOutOfRange { index: usize, len: usize },
+
+    #[display(fmt = "Expected a tuple of {} elements but got {}", expected, found)]
+    TupleArityMismatch { expected: usize, found: usize },
+
+    #[display(fmt = "Invalid date: {}", _0)]
+    InvalidDate(String),
+
+    #[display(fmt = "Invalid calendar: {}", _0)]
+    InvalidCalendar(String),
 }
 
 impl ToReport for Error {
@@ -0,0 +1,177 @@
+use crate::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FormatSpec {
+    fill: char,
+    align: Option<Align>,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+impl Default for FormatSpec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            width: None,
+            precision: None,
+        }
+    }
+}
+
+fn parse_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut result = Self::default();
+        let chars = spec.chars().collect::<Vec<_>>();
+        let mut i = 0;
+
+        match (
+            chars.first().copied(),
+            chars.get(1).copied().and_then(parse_align),
+        ) {
+            (Some(fill), Some(align)) => {
+                result.fill = fill;
+                result.align = Some(align);
+                i = 2;
+            }
+            (Some(c), _) if parse_align(c).is_some() => {
+                result.align = parse_align(c);
+                i = 1;
+            }
+            _ => (),
+        }
+
+        let width = chars[i..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>();
+        if !width.is_empty() {
+            i += width.len();
+            result.width = width.parse().ok();
+        }
+
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            let precision = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>();
+            if precision.is_empty() {
+                return Err(format!("missing precision in `{}`", spec));
+            }
+            i += precision.len();
+            result.precision = precision.parse().ok();
+        }
+
+        if i != chars.len() {
+            return Err(format!("unknown format option `{}`", spec));
+        }
+
+        Ok(result)
+    }
+
+    fn apply(&self, value: &Value) -> String {
+        let text = match (value, self.precision) {
+            (Value::Number(n), Some(precision)) => format!("{:.*}", precision, *n as f64),
+            // Like Rust, precision truncates anything that isn't a number.
+            (_, Some(precision)) => interpolate(value).chars().take(precision).collect(),
+            (_, None) => interpolate(value),
+        };
+
+        let len = text.chars().count();
+        let width = match self.width {
+            Some(width) if width > len => width,
+            _ => return text,
+        };
+
+        let align = self.align.unwrap_or(match value {
+            Value::Number(_) => Align::Right,
+            _ => Align::Left,
+        });
+
+        let padding = width - len;
+        let (left, right) = match align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+        format!("{}{}{}", fill(left), text, fill(right))
+    }
+}
+
+// Strings go in without their quotes, `format`, `${…}` and `to_string` all agree on that.
+pub(crate) fn interpolate(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+pub(crate) fn format(template: &str, args: &[Value]) -> Result<String, String> {
+    let mut output = String::new();
+    let mut next_arg = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => return Err("unmatched `}`".into()),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err("unclosed `{`".into()),
+                    }
+                }
+
+                let mut parts = placeholder.splitn(2, ':');
+                let index = parts.next().unwrap_or_default();
+                let spec = FormatSpec::parse(parts.next().unwrap_or_default())?;
+
+                let index = if index.is_empty() {
+                    next_arg += 1;
+                    next_arg - 1
+                } else {
+                    index
+                        .parse()
+                        .map_err(|_| format!("invalid argument index `{}`", index))?
+                };
+
+                let arg = args
+                    .get(index)
+                    .ok_or_else(|| format!("missing argument {}", index))?;
+                output.push_str(&spec.apply(arg));
+            }
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
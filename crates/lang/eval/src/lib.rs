@@ -1,29 +1,50 @@
+mod builtins;
+mod date;
 mod error;
+mod format;
 mod markdown;
+mod matching;
 mod result;
+mod table;
 mod value;
 
 pub mod db;
 
+use crate::db::Evaluator;
+pub use date::{Calendar, Date, Era, Month};
 use error::Error;
+use indexmap::IndexMap;
+pub use markdown::{MdAlign, MdKind, MdNode};
 use neu_diagnostics::{Diagnostic, ToReport, Diagnostics};
 use neu_parser::{Arena, Children, Node, NodeId};
+use neu_syntax::ast::{Ast, Import};
+use neu_syntax::db::FileId;
+use neu_syntax::lexers::string::unescape;
 use neu_syntax::Nodes;
-use std::collections::BTreeMap;
-pub use value::Value;
+use std::cmp::Ordering;
+pub use table::{Rng, Table, TableEntry};
+pub use value::{EnumDecl, Value, Variant, VariantDecl};
 
 pub struct Eval<'a> {
+    pub db: &'a dyn Evaluator,
+    pub file: FileId,
     pub arena: &'a Arena,
     pub errors: Diagnostics<NodeId>,
     pub input: &'a str,
+    locals: Vec<(String, Value)>,
+    optional: bool,
 }
 
 impl<'a> Eval<'a> {
-    pub fn new(arena: &'a Arena, input: &'a str) -> Self {
+    pub fn new(db: &'a dyn Evaluator, file: FileId, arena: &'a Arena, input: &'a str) -> Self {
         Self {
+            db,
+            file,
             arena,
             errors: Default::default(),
             input,
+            locals: vec![],
+            optional: false,
         }
     }
 
@@ -44,7 +65,7 @@ impl<'a> Eval<'a> {
                         let v = self.into_eager(v, recursive);
                         v.map(|v| (k, v))
                     })
-                    .collect::<Option<BTreeMap<String, Value>>>()?;
+                    .collect::<Option<IndexMap<String, Value>>>()?;
                 Some(Value::Struct(s))
             }
             Value::Array(a) => {
@@ -54,6 +75,13 @@ impl<'a> Eval<'a> {
                     .collect::<Option<Vec<Value>>>()?;
                 Some(Value::Array(a))
             }
+            Value::Tuple(t) => {
+                let t = t
+                    .into_iter()
+                    .map(|v| self.into_eager(v, recursive))
+                    .collect::<Option<Vec<Value>>>()?;
+                Some(Value::Tuple(t))
+            }
             v => Some(v),
         }
     }
@@ -63,20 +91,56 @@ impl<'a> Eval<'a> {
         self.into_eager(v, recursive)
     }
 
+    fn report(&mut self, id: NodeId, error: Error) {
+        let err: Diagnostic = error.to_report(self.input);
+        self.errors.add(id, err);
+    }
+
     fn expect_some<V>(&mut self, id: NodeId, v: Option<V>, error: Error) -> Option<V> {
         match v {
             Some(v) => Some(v),
             None => {
-                let input = self.input;
-                let err: Diagnostic = error.to_report(input);
-                self.errors.add(id, err);
+                self.report(id, error);
                 None
             }
         }
     }
 
-    fn eval_identifier(&mut self, id: NodeId, node: &Node) -> Option<Value> {
+    fn find_import(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        let root = self.arena.ancestors(id).last()?;
+        self.arena
+            .get(root)
+            .children
+            .iter()
+            .copied()
+            .filter_map(|child| Import::from_syntax(child, self.arena))
+            .find(|import| import.alias(self.arena, self.input) == Some(name))
+            .map(|import| import.id)
+    }
+
+    fn eval_import(&mut self, id: NodeId) -> Option<Value> {
+        let import = Import::from_syntax(id, self.arena)?;
+        let path = import.path(self.arena, self.input)?;
+        let target = self.db.resolve_import(self.file, path.clone());
+        let target = self.expect_some(id, target, Error::ImportNotFound(path))?;
+        if db::imports_reach(self.db, target, self.file) {
+            self.report(id, Error::ImportCycle);
+            return None;
+        }
+
+        let parsed = self.db.parse_syntax(target);
+        let imported = self.db.eval(target, parsed.root);
+        imported.value.clone()
+    }
+
+    fn eval_identifier(&mut self, id: NodeId, node: &Node, optional: bool) -> Option<Value> {
         let text = &self.input[node.span];
+        if let Some((_, local)) = self.locals.iter().rev().find(|(name, _)| name == text) {
+            return Some(local.clone());
+        }
+        if let Some(import) = self.find_import(id, text) {
+            return self.eval(import);
+        }
         let top = self
             .arena
             .ancestors(id)
@@ -85,19 +149,55 @@ impl<'a> Eval<'a> {
         let top = self.expect_some(id, top, Error::ContextNotFound)?;
         let top = self.eval(top)?;
         let mut map = self.expect_some(id, top.into_struct(), Error::ValueNotStruct)?;
-        self.expect_some(id, map.remove(text), Error::FieldNotFound)
+        self.field(id, map.remove(text), optional)
     }
 
-    fn eval_ident_path(&mut self, node: &Node) -> Option<Value> {
+    fn field(&mut self, id: NodeId, value: Option<Value>, optional: bool) -> Option<Value> {
+        match value {
+            None if optional => Some(Value::Null),
+            value => self.expect_some(id, value, Error::FieldNotFound),
+        }
+    }
+
+    fn eval_ident_path(&mut self, node: &Node, optional: bool) -> Option<Value> {
         let mut children = Children::new(node.children.iter().copied(), self.arena);
         let (left_id, _) = children.find_node(Nodes::Value)?;
+        self.optional = optional;
         let left = self.eager_eval(left_id, false)?;
-        let _ = children.find_node(Nodes::Op)?;
-        let (right_id, right) = children.find_node(Nodes::Identifier)?;
+        let (_, op) = children.find_node(Nodes::Op)?;
+        let optional = optional || &self.input[op.span] == "?.";
+        let (right_id, right) = children.find_node(Nodes::Value)?;
+        if let Value::Enum(decl) = left {
+            return self.eval_variant(decl, right_id, right);
+        }
+        // `a?.b.c` short-circuits on the first null instead of failing on `.c`.
+        if (optional || self.safe_navigation(left_id)) && left.is_null() {
+            return Some(Value::Null);
+        }
+        if right.is(Nodes::Number) {
+            let index = self.input[right.span].parse().ok()?;
+            let tuple = self.expect_some(left_id, left.into_tuple(), Error::ValueNotTuple)?;
+            let len = tuple.len();
+            let error = Error::TupleIndexOutOfRange { index, len };
+            return self.expect_some(right_id, tuple.into_iter().nth(index), error);
+        }
+        if !right.is(Nodes::Identifier) {
+            return None;
+        }
         let key = &self.input[right.span];
 
         let mut map = self.expect_some(left_id, left.into_struct(), Error::ValueNotStruct)?;
-        self.expect_some(right_id, map.remove(key), Error::FieldNotFound)
+        self.field(right_id, map.remove(key), optional)
+    }
+
+    fn safe_navigation(&self, id: NodeId) -> bool {
+        let node = self.arena.get(id);
+        node.is(Nodes::IdentPath)
+            && node.children.iter().any(|child| {
+                let child_node = self.arena.get(child);
+                (child_node.is(Nodes::Op) && &self.input[child_node.span] == "?.")
+                    || self.safe_navigation(*child)
+            })
     }
 
     fn eval_self_ident_path(
@@ -105,6 +205,7 @@ impl<'a> Eval<'a> {
         op_id: NodeId,
         value_id: NodeId,
         value: &Node,
+        optional: bool,
     ) -> Option<Value> {
         let text = &self.input[value.span];
         let current = self
@@ -114,10 +215,10 @@ impl<'a> Eval<'a> {
         let current = self.expect_some(op_id, current, Error::ContextNotFound)?;
         let current = self.eval(current)?;
         let mut map = self.expect_some(op_id, current.into_struct(), Error::ValueNotStruct)?;
-        self.expect_some(value_id, map.remove(text), Error::FieldNotFound)
+        self.field(value_id, map.remove(text), optional)
     }
 
-    fn eval_unary(&mut self, node: &Node) -> Option<Value> {
+    fn eval_unary(&mut self, node: &Node, optional: bool) -> Option<Value> {
         let mut children = Children::new(node.children.iter().copied(), self.arena);
         let (op_id, op) = children.find_node(Nodes::Op)?;
         let text_op = &self.input[op.span];
@@ -125,7 +226,7 @@ impl<'a> Eval<'a> {
         let (value_id, value) = children.find_node(Nodes::Value)?;
 
         if text_op == "." {
-            return self.eval_self_ident_path(op_id, value_id, value);
+            return self.eval_self_ident_path(op_id, value_id, value, optional);
         }
 
         let value = self.eager_eval(value_id, false)?;
@@ -139,28 +240,59 @@ impl<'a> Eval<'a> {
     fn eval_binary(&mut self, node: &Node) -> Option<Value> {
         let mut children = Children::new(node.children.iter().copied(), self.arena);
         let (left, _) = children.find_node(Nodes::Value)?;
-        let left = self.eager_eval(left, false)?;
-        let (_, op) = children.find_node(Nodes::Op)?;
-        let (right, _) = children.find_node(Nodes::Value)?;
-        let right = self.eager_eval(right, false)?;
+        let (op_id, op) = children.find_node(Nodes::Op)?;
         let text_op = &self.input[op.span];
+        if text_op == "??" {
+            self.optional = true;
+            let left = self.eager_eval(left, false)?;
+            if !left.is_null() {
+                return Some(left);
+            }
+            let (right, _) = children.find_node(Nodes::Value)?;
+            return self.eager_eval(right, false);
+        }
+        let recursive = text_op == "==" || text_op == "!=";
+        let left = self.eager_eval(left, recursive)?;
+        let (right, _) = children.find_node(Nodes::Value)?;
+        let right = self.eager_eval(right, recursive)?;
         match (left, text_op, right) {
             (Value::Number(l), "-", Value::Number(r)) => Some(Value::Number(l - r)),
             (Value::Number(l), "+", Value::Number(r)) => Some(Value::Number(l + r)),
             (Value::Number(l), "*", Value::Number(r)) => Some(Value::Number(l * r)),
             (Value::Number(l), "/", Value::Number(r)) => Some(Value::Number(l / r)),
-            (Value::Boolean(l), "==", Value::Boolean(r)) => Some(Value::Boolean(l == r)),
-            _ => unreachable!(),
+            (Value::String(l), "+", Value::String(r)) => Some(Value::String(l + &r)),
+            (l, "==", r) => Some(Value::Boolean(l == r)),
+            (l, "!=", r) => Some(Value::Boolean(l != r)),
+            (Value::Number(l), op, Value::Number(r)) if is_comparison(op) => {
+                Some(Value::Boolean(compare(op, l.cmp(&r))))
+            }
+            (Value::String(l), op, Value::String(r)) if is_comparison(op) => {
+                Some(Value::Boolean(compare(op, l.cmp(&r))))
+            }
+            (Value::Date(l), op, Value::Date(r)) if is_comparison(op) => {
+                Some(Value::Boolean(compare(op, l.cmp(&r))))
+            }
+            (l, op, r) => {
+                let error = Error::InvalidOperands {
+                    op: op.into(),
+                    left: l.kind(),
+                    right: r.kind(),
+                };
+                self.expect_some(op_id, None, error)
+            }
         }
     }
 
     pub fn eval(&mut self, id: NodeId) -> Option<Value> {
         let node = self.arena.get(id);
+        let optional = std::mem::replace(&mut self.optional, false);
 
         if node.is(Nodes::Root) {
+            let arena = self.arena;
             return node
                 .children
                 .iter()
+                .filter(|child| !arena.get(*child).is(Nodes::Import))
                 .filter_map(|child| self.eval(*child))
                 .next();
         }
@@ -171,24 +303,52 @@ impl<'a> Eval<'a> {
         let mut children = Children::new(node.children.iter().copied(), self.arena);
         let text = &self.input[node.span];
 
+        if node.is(Nodes::Import) {
+            return self.eval_import(id);
+        }
         if node.is(Nodes::Identifier) {
-            return self.eval_identifier(id, node);
+            return self.eval_identifier(id, node, optional);
         }
         if node.is(Nodes::IdentPath) {
-            return self.eval_ident_path(node);
+            return self.eval_ident_path(node, optional);
         }
         if node.is(Nodes::Number) {
             return Some(Value::Number(text.parse().unwrap()));
         }
+        if node.is(Nodes::Date) {
+            let date = text[1..].parse().map_err(Error::InvalidDate);
+            return match date {
+                Ok(date) => Some(Value::Date(date)),
+                Err(error) => {
+                    self.report(id, error);
+                    None
+                }
+            };
+        }
         if node.is(Nodes::Boolean) {
             return Some(Value::Boolean(text == "true"));
         }
+        if node.is(Nodes::Null) {
+            return Some(Value::Null);
+        }
         if node.is(Nodes::Unary) {
-            return self.eval_unary(node);
+            return self.eval_unary(node, optional);
         }
         if node.is(Nodes::Binary) {
             return self.eval_binary(node);
         }
+        if node.is(Nodes::Call) {
+            return self.eval_call(node);
+        }
+        if node.is(Nodes::Enum) {
+            return self.eval_enum(node);
+        }
+        if node.is(Nodes::Match) {
+            return self.eval_match(id, node);
+        }
+        if node.is(Nodes::Let) {
+            return self.eval_let(node);
+        }
 
         if node.is(Nodes::Array) {
             let mut values = vec![];
@@ -199,27 +359,45 @@ impl<'a> Eval<'a> {
             return Some(Value::Array(values));
         }
 
+        if node.is(Nodes::Tuple) {
+            let mut values = vec![];
+            while let Some((value, _)) = children.find_node(Nodes::Value) {
+                let value = self.eval(value)?;
+                values.push(value);
+            }
+            return Some(Value::Tuple(values));
+        }
+
         if node.is(Nodes::Struct) {
-            let mut map = BTreeMap::default();
-            while let Some((_, key)) = children.find_node(Nodes::Key) {
-                let key = self.input[key.span].to_string();
-                let (value, _) = children.find_node(Nodes::Value)?;
-                let value = Value::Lazy { id: value };
-                map.insert(key, value);
+            let mut map = IndexMap::default();
+            let mut key = None;
+            for (child_id, child) in children {
+                if child.is(Nodes::Import) {
+                    let import = Import::from_syntax(child_id, self.arena)?;
+                    if let Some(alias) = import.alias(self.arena, self.input) {
+                        map.insert(alias.to_string(), Value::Lazy { id: child_id });
+                    }
+                } else if child.is(Nodes::Key) {
+                    key = Some(self.input[child.span].to_string());
+                } else if child.is(Nodes::Value) {
+                    if let Some(key) = key.take() {
+                        map.insert(key, Value::Lazy { id: child_id });
+                    }
+                }
             }
             return Some(Value::Struct(map));
         }
 
         if node.is(Nodes::Markdown) {
-            let mut s = String::new();
+            let mut nodes = vec![];
             if node.is(Nodes::Md_Value) {
-                self.eval_md(&mut s, node)?;
+                nodes.extend(self.eval_md(id, node)?);
             } else {
-                while let Some((_, value)) = children.find_node(Nodes::Md_Value) {
-                    self.eval_md(&mut s, value)?;
+                while let Some((value_id, value)) = children.find_node(Nodes::Md_Value) {
+                    nodes.extend(self.eval_md(value_id, value)?);
                 }
             }
-            return Some(Value::String(s));
+            return Some(Value::Markdown(nodes));
         }
 
         if node.is(Nodes::String) {
@@ -229,9 +407,9 @@ impl<'a> Eval<'a> {
                     let mut children = Children::new(value.children.iter().copied(), self.arena);
                     let (value_id, _) = children.find_node(Nodes::Value)?;
                     let value = self.eager_eval(value_id, true)?;
-                    s += &value.to_string();
+                    s += &format::interpolate(&value);
                 } else {
-                    s += &self.input[value.span];
+                    s += &unescape(&self.input[value.span]);
                 }
             }
             return Some(Value::String(s));
@@ -245,10 +423,24 @@ impl<'a> Eval<'a> {
     }
 }
 
+fn is_comparison(op: &str) -> bool {
+    matches!(op, "<" | "<=" | ">" | ">=")
+}
+
+fn compare(op: &str, ordering: Ordering) -> bool {
+    match op {
+        "<" => ordering == Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        ">=" => ordering != Ordering::Less,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::db::Evaluator;
-    use neu_syntax::db::{FileKind, Parser};
+    use neu_syntax::db::{load_files, FileKind, Parser};
     use std::sync::Arc;
 
     #[salsa::database(crate::db::EvaluatorDatabase, neu_syntax::db::ParserDatabase)]
@@ -274,4 +466,111 @@ mod tests {
         })
         .unwrap();
     }
+
+    fn import_db(files: &[(&str, &str)]) -> TestDb {
+        let mut db = TestDb::default();
+        load_files(&mut db, files);
+        db
+    }
+
+    fn eval_file(db: &TestDb, path: &str) -> (String, Vec<String>) {
+        let file = db.file_id((path.into(), FileKind::Neu));
+        let parsed = db.parse_syntax(file);
+        let result = db.eval(file, parsed.root);
+        let value = result
+            .value
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "None".into());
+        let errors = result
+            .errors
+            .iter()
+            .map(|(_, error)| error.clone())
+            .collect();
+        (value, errors)
+    }
+
+    #[test]
+    fn import_relative_to_importing_file() {
+        let db = import_db(&[
+            (
+                "campaign/main.neu",
+                r#"import "rules/combat.neu" as combat
+combat.dmg + 1"#,
+            ),
+            ("campaign/rules/combat.neu", "{ dmg = 5 }"),
+        ]);
+
+        assert_eq!(eval_file(&db, "campaign/main.neu"), ("6".into(), vec![]));
+    }
+
+    #[test]
+    fn import_in_struct_field() {
+        let db = import_db(&[
+            (
+                "a/main.neu",
+                r#"{ import "../b/stats.neu" as stats, hp = stats.hp * 2 }"#,
+            ),
+            ("b/stats.neu", "{ hp = 10 }"),
+        ]);
+
+        assert_eq!(
+            eval_file(&db, "a/main.neu"),
+            ("{ stats = { hp = 10 }, hp = 20 }".into(), vec![])
+        );
+    }
+
+    #[test]
+    fn import_not_found() {
+        let db = import_db(&[(
+            "main.neu",
+            r#"import "missing.neu" as m
+m.x"#,
+        )]);
+
+        assert_eq!(
+            eval_file(&db, "main.neu"),
+            (
+                "None".into(),
+                vec!["Couldn't find imported file `missing.neu`".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn import_cycle() {
+        let db = import_db(&[
+            (
+                "a.neu",
+                r#"import "b.neu" as b
+b.x"#,
+            ),
+            (
+                "b.neu",
+                r#"import "a.neu" as a
+{ x = a }"#,
+            ),
+        ]);
+
+        assert_eq!(
+            eval_file(&db, "a.neu"),
+            ("None".into(), vec!["Import cycle detected".into()])
+        );
+    }
+
+    #[test]
+    fn calendar_month_days_must_fit() {
+        let db = import_db(&[(
+            "calendar.neu",
+            r#"{ months = [{ name = "Long", days = 4294967297 }] }"#,
+        )]);
+        let file = db.calendar_file().expect("calendar.neu");
+        let parsed = db.parse_syntax(file);
+        let value = db.eval(file, parsed.root).value.clone().expect("value");
+
+        assert_eq!(
+            crate::Calendar::from_value(&value),
+            Err("Invalid calendar: `Long` has 4294967297 days".into())
+        );
+    }
 }
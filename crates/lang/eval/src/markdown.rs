@@ -1,122 +1,318 @@
-use crate::Eval;
-use neu_parser::{Children, Name, Node};
+use crate::{Eval, Value};
+use neu_parser::{Children, Name, Node, NodeId};
+use neu_syntax::db::FileId;
 use neu_syntax::Nodes;
-use regex::Regex;
+use std::fmt;
 
-impl<'a> Eval<'a> {
-    pub(crate) fn eval_md(&mut self, str: &mut String, node: &Node) -> Option<()> {
-        const TAGS: &[(Name, &str)] = &[
-            (Nodes::Md_Paragraph, "p"),
-            (Nodes::Md_H1, "h1"),
-            (Nodes::Md_H2, "h2"),
-            (Nodes::Md_H3, "h3"),
-            (Nodes::Md_H4, "h4"),
-            (Nodes::Md_H5, "h5"),
-            (Nodes::Md_H6, "h6"),
-            (Nodes::Md_Emphasis, "em"),
-            (Nodes::Md_Strong, "strong"),
-            (Nodes::Md_BlockQuote, "blockquote"),
-            (Nodes::Md_UnorderedList, "ul"),
-            (Nodes::Md_OrderedList, "ol"),
-            (Nodes::Md_ListItem, "li"),
-        ];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MdAlign {
+    Left,
+    Center,
+    Right,
+}
 
-        let mut end_vec = vec![];
-        for (tag_name, tag_str) in TAGS {
-            if node.is(*tag_name) {
-                str.push_str(&format!("<{}>", tag_str));
-                end_vec.push(format!("</{}>", tag_str));
-            }
-        }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MdKind {
+    Paragraph,
+    Heading(u8),
+    Emphasis,
+    Strong,
+    Strikethrough,
+    BlockQuote,
+    UnorderedList,
+    OrderedList,
+    ListItem,
+    CodeBlock(Option<String>),
+    Link(String),
+    WikiLink {
+        kind: String,
+        item_id: String,
+        label: Option<String>,
+        secret: bool,
+    },
+    Image(String),
+    Table,
+    TableHead,
+    TableRow,
+    TableCell {
+        head: bool,
+        align: Option<MdAlign>,
+    },
+    FootnoteReference(String),
+    FootnoteDefinition(String),
+    TaskListMarker(bool),
+    Rule,
+    SoftBreak,
+    HardBreak,
+    Text(String),
+    // `id` belongs to the arena of `file`, which isn't the rendered one for imported markdown.
+    Html {
+        file: FileId,
+        id: NodeId,
+        html: String,
+    },
+    Code(Box<Value>),
+    CodeShow {
+        source: String,
+        value: Box<Value>,
+    },
+    Value(Box<Value>),
+}
 
-        if node.is(Nodes::Md_CodeBlock) {
-            let mut children = Children::new(node.children.iter().copied(), self.arena);
-            str.push_str("<pre><code");
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdNode {
+    pub kind: MdKind,
+    pub children: Vec<MdNode>,
+}
 
-            if let Some((_, node)) = children.find_node(Nodes::Md_CodeBlockLang) {
-                let text = &self.input[node.span];
-                str.push_str(&format!(r#" class="language-{}""#, text));
-            }
-            str.push_str(">");
-            end_vec.push("</code></pre>".into());
+impl MdNode {
+    pub fn leaf(kind: MdKind) -> Self {
+        Self {
+            kind,
+            children: vec![],
         }
+    }
+}
 
-        if node.is(Nodes::Md_Image) {
-            let mut children = Children::new(node.children.iter().copied(), self.arena);
-            str.push_str("<img");
-            if let Some((_, node)) = children.find_node(Nodes::Md_ImageSrc) {
-                let text = &self.input[node.span];
-                str.push_str(&format!(r#" src="{}""#, text));
+impl fmt::Display for MdNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            MdKind::Text(text) => return write!(f, "{:?}", text),
+            MdKind::Paragraph => write!(f, "paragraph")?,
+            MdKind::Heading(level) => write!(f, "h{}", level)?,
+            MdKind::Emphasis => write!(f, "emphasis")?,
+            MdKind::Strong => write!(f, "strong")?,
+            MdKind::Strikethrough => write!(f, "strikethrough")?,
+            MdKind::BlockQuote => write!(f, "blockquote")?,
+            MdKind::UnorderedList => write!(f, "list")?,
+            MdKind::OrderedList => write!(f, "ordered_list")?,
+            MdKind::ListItem => write!(f, "item")?,
+            MdKind::CodeBlock(None) => write!(f, "code_block")?,
+            MdKind::CodeBlock(Some(lang)) => write!(f, "code_block({:?})", lang)?,
+            MdKind::Link(url) => write!(f, "link({:?})", url)?,
+            MdKind::WikiLink {
+                kind,
+                item_id,
+                label,
+                secret,
+                ..
+            } => {
+                let prefix = if *secret { "secret:" } else { "" };
+                write!(f, "wiki_link(\"{}{}:{}\"", prefix, kind, item_id)?;
+                if let Some(label) = label {
+                    write!(f, ", {:?}", label)?;
+                }
+                write!(f, ")")?
             }
-            if let Some((_, _node)) = children.find_node(Nodes::Md_ImageTitle) {
-                //let text = &self.input[node.span];
-                //str.push_str(&format!(r#" title="{}""#, text));
-                //TODO: Disabled title:
-                // Because of Pulldown cmark using inline string which loses
-                // information about the title span.
+            MdKind::Image(src) => write!(f, "image({:?})", src)?,
+            MdKind::Table => write!(f, "table")?,
+            MdKind::TableHead => write!(f, "table_head")?,
+            MdKind::TableRow => write!(f, "table_row")?,
+            MdKind::TableCell { head, align } => {
+                write!(f, "{}", if *head { "head_cell" } else { "cell" })?;
+                match align {
+                    Some(MdAlign::Left) => write!(f, "(left)")?,
+                    Some(MdAlign::Center) => write!(f, "(center)")?,
+                    Some(MdAlign::Right) => write!(f, "(right)")?,
+                    None => (),
+                }
+            }
+            MdKind::FootnoteReference(label) => write!(f, "footnote_ref({:?})", label)?,
+            MdKind::FootnoteDefinition(label) => write!(f, "footnote({:?})", label)?,
+            MdKind::TaskListMarker(checked) => write!(f, "task({})", checked)?,
+            MdKind::Rule => write!(f, "rule")?,
+            MdKind::SoftBreak => write!(f, "soft_break")?,
+            MdKind::HardBreak => write!(f, "hard_break")?,
+            MdKind::Html { html, .. } => write!(f, "html({:?})", html)?,
+            MdKind::Code(value) => write!(f, "code({})", value)?,
+            MdKind::CodeShow { source, value } => write!(f, "code_show({:?}, {})", source, value)?,
+            MdKind::Value(value) => write!(f, "value({})", value)?,
+        }
+        if !self.children.is_empty() {
+            write!(f, "[")?;
+            for (idx, child) in self.children.iter().enumerate() {
+                let sep = if idx == 0 { "" } else { ", " };
+                write!(f, "{}{}", sep, child)?;
             }
-            str.push_str(">");
-            end_vec.push("</img>".into());
+            write!(f, "]")?;
         }
+        Ok(())
+    }
+}
 
-        if node.is(Nodes::Md_Link) {
+impl<'a> Eval<'a> {
+    pub(crate) fn eval_md(&mut self, id: NodeId, node: &Node) -> Option<Vec<MdNode>> {
+        let mut nodes = if node.is(Nodes::Interpolated) {
+            let value = Box::new(self.eval_md_value(node)?);
+            let kind = if node.is(Nodes::Md_CodeShow) {
+                let source = self.input[node.span].to_string();
+                MdKind::CodeShow { source, value }
+            } else {
+                MdKind::Code(value)
+            };
+            vec![MdNode::leaf(kind)]
+        } else if node.is(Nodes::Md_Interpolation) {
             let mut children = Children::new(node.children.iter().copied(), self.arena);
-            str.push_str("<a");
-            if let Some((_, node)) = children.find_node(Nodes::Md_LinkUrl) {
-                let text = &self.input[node.span];
-                //TODO Parse :
-                let link_regex = Regex::new("([a-z_A-Z0-9]+):([0-9A-Fa-f]{8})").expect("Regex");
-                if let Some(cap) = link_regex.captures(text) {
-                    let kind = cap.get(1).expect("G1").as_str();
-                    let id = cap.get(2).expect("G2").as_str();
-                    str.push_str(&format!(r#" href="/{}/{}""#, kind, id));
-                } else {
-                    str.push_str(&format!(r#" href="{}""#, text));
-                }
+            let (_, inner) = children.find_node(Nodes::Interpolated)?;
+            let value = self.eval_md_value(inner)?;
+            vec![MdNode::leaf(MdKind::Value(Box::new(value)))]
+        } else {
+            let mut children = vec![];
+            for child_id in node.children.iter() {
+                let child = self.arena.get(child_id);
+                children.extend(self.eval_md(*child_id, child)?);
             }
-            if let Some((_, _node)) = children.find_node(Nodes::Md_LinkTitle) {
-                //let text = &self.input[node.span];
-                //str.push_str(&format!(r#" title="{}""#, text));
-                //TODO: Disabled title:
-                // Because of Pulldown cmark using inline string which loses
-                // information about the title span.
-            }
-            str.push_str(">");
-            end_vec.push("</a>".into());
+            children
+        };
+
+        // One syntax node can carry several names (e.g. a paragraph with only emphasis),
+        // so the kinds are nested from the innermost one outwards.
+        for kind in self.md_kinds(id, node).into_iter().rev() {
+            nodes = vec![MdNode {
+                kind,
+                children: nodes,
+            }];
         }
+        Some(nodes)
+    }
+
+    fn eval_md_value(&mut self, node: &Node) -> Option<Value> {
+        let mut children = Children::new(node.children.iter().copied(), self.arena);
+        let (value_id, _) = children.find_node(Nodes::Value)?;
+        self.eager_eval(value_id, true)
+    }
+
+    fn md_child_text(&self, node: &Node, name: Name) -> Option<String> {
+        let mut children = Children::new(node.children.iter().copied(), self.arena);
+        let (_, child) = children.find_node(name)?;
+        Some(self.input[child.span].to_string())
+    }
 
+    fn md_kinds(&self, id: NodeId, node: &Node) -> Vec<MdKind> {
+        const KINDS: &[(Name, MdKind)] = &[
+            (Nodes::Md_Paragraph, MdKind::Paragraph),
+            (Nodes::Md_H1, MdKind::Heading(1)),
+            (Nodes::Md_H2, MdKind::Heading(2)),
+            (Nodes::Md_H3, MdKind::Heading(3)),
+            (Nodes::Md_H4, MdKind::Heading(4)),
+            (Nodes::Md_H5, MdKind::Heading(5)),
+            (Nodes::Md_H6, MdKind::Heading(6)),
+            (Nodes::Md_Emphasis, MdKind::Emphasis),
+            (Nodes::Md_Strong, MdKind::Strong),
+            (Nodes::Md_BlockQuote, MdKind::BlockQuote),
+            (Nodes::Md_UnorderedList, MdKind::UnorderedList),
+            (Nodes::Md_OrderedList, MdKind::OrderedList),
+            (Nodes::Md_ListItem, MdKind::ListItem),
+            (Nodes::Md_Strikethrough, MdKind::Strikethrough),
+            (Nodes::Md_Table, MdKind::Table),
+            (Nodes::Md_TableRow, MdKind::TableRow),
+            (Nodes::Md_TableHead, MdKind::TableHead),
+        ];
+
+        let mut kinds = KINDS
+            .iter()
+            .filter(|(name, _)| node.is(*name))
+            .map(|(_, kind)| kind.clone())
+            .collect::<Vec<_>>();
+
+        if node.is(Nodes::Md_TableCell) {
+            let align = if node.is(Nodes::Md_AlignLeft) {
+                Some(MdAlign::Left)
+            } else if node.is(Nodes::Md_AlignCenter) {
+                Some(MdAlign::Center)
+            } else if node.is(Nodes::Md_AlignRight) {
+                Some(MdAlign::Right)
+            } else {
+                None
+            };
+            let head = node.is(Nodes::Md_TableHeadCell);
+            kinds.push(MdKind::TableCell { head, align });
+        }
+        if node.is(Nodes::Md_FootnoteReference) {
+            if let Some(label) = self.md_child_text(node, Nodes::Md_FootnoteLabel) {
+                kinds.push(MdKind::FootnoteReference(label));
+            }
+        }
+        if node.is(Nodes::Md_FootnoteDefinition) {
+            if let Some(label) = self.md_child_text(node, Nodes::Md_FootnoteLabel) {
+                kinds.push(MdKind::FootnoteDefinition(label));
+            }
+        }
+        if node.is(Nodes::Md_TaskListMarker) {
+            kinds.push(MdKind::TaskListMarker(node.is(Nodes::Md_Checked)));
+        }
+        if node.is(Nodes::Md_CodeBlock) {
+            let lang = self.md_child_text(node, Nodes::Md_CodeBlockLang);
+            kinds.push(MdKind::CodeBlock(lang));
+        }
+        if node.is(Nodes::Md_Image) {
+            let src = self.md_child_text(node, Nodes::Md_ImageSrc);
+            kinds.push(MdKind::Image(src.unwrap_or_default()));
+        }
+        if node.is(Nodes::Md_Link) {
+            let url = self.md_child_text(node, Nodes::Md_LinkUrl);
+            kinds.push(MdKind::Link(url.unwrap_or_default()));
+        }
+        if node.is(Nodes::Md_WikiLink) {
+            let kind = self.md_child_text(node, Nodes::Md_WikiKind);
+            let item_id = self.md_child_text(node, Nodes::Md_WikiId);
+            kinds.push(MdKind::WikiLink {
+                kind: kind.unwrap_or_default(),
+                item_id: item_id.unwrap_or_default(),
+                label: self.md_child_text(node, Nodes::Md_WikiLabel),
+                secret: self.md_child_text(node, Nodes::Md_WikiSecret).is_some(),
+            });
+        }
         if node.is(Nodes::Md_Rule) {
-            str.push_str("<hr/>")
+            kinds.push(MdKind::Rule);
         }
-
         if node.is(Nodes::Md_SoftBreak) {
-            str.push_str("\n");
+            kinds.push(MdKind::SoftBreak);
         }
-
-        if node.is(Nodes::Md_Text) || node.is(Nodes::Md_Html) {
+        if node.is(Nodes::Md_HardBreak) {
+            kinds.push(MdKind::HardBreak);
+        }
+        if node.is(Nodes::Md_Text) {
             let text = &self.input[node.span];
-            str.push_str(text);
+            kinds.push(MdKind::Text(match decode_entity(text) {
+                Some(c) => c.to_string(),
+                None => text.to_string(),
+            }));
         }
-
-        if node.is(Nodes::Interpolated) {
-            let mut children = Children::new(node.children.iter().copied(), self.arena);
-            let (value_id, _) = children.find_node(Nodes::Value)?;
-            let value = self.eager_eval(value_id, true)?;
-
-            str.push_str("<pre><code>");
-            str.push_str(&format!("{:#}", value));
-            end_vec.push("</code></pre>".into());
+        if node.is(Nodes::Md_Html) {
+            let html = self.input[node.span].to_string();
+            kinds.push(MdKind::Html {
+                file: self.file,
+                id,
+                html,
+            });
         }
 
-        for id in node.children.iter() {
-            let child = self.arena.get(id);
-            self.eval_md(str, child)?;
-        }
-
-        for end in end_vec.into_iter().rev() {
-            str.push_str(&end);
-        }
+        kinds
+    }
+}
 
-        Some(())
+// The parser keeps `&amp;` and the like as separate text nodes spanning the reference.
+fn decode_entity(text: &str) -> Option<char> {
+    if !text.starts_with('&') || !text.ends_with(';') || text.len() < 3 {
+        return None;
+    }
+    let name = &text[1..text.len() - 1];
+    if name.starts_with("#x") || name.starts_with("#X") {
+        return u32::from_str_radix(&name[2..], 16)
+            .ok()
+            .and_then(std::char::from_u32);
+    }
+    if name.starts_with('#') {
+        return name[1..].parse().ok().and_then(std::char::from_u32);
+    }
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => None,
     }
 }
@@ -0,0 +1,307 @@
+use crate::error::Error;
+use crate::{EnumDecl, Eval, Value, Variant, VariantDecl};
+use neu_parser::{Children, Node, NodeId};
+use neu_syntax::Nodes;
+use std::sync::Arc;
+
+enum Pattern {
+    Wildcard,
+    Binding(String),
+    Literal(NodeId),
+    Variant {
+        path: Vec<String>,
+        bindings: Vec<Option<String>>,
+    },
+}
+
+impl<'a> Eval<'a> {
+    pub(crate) fn eval_enum(&mut self, node: &Node) -> Option<Value> {
+        let mut name = None;
+        let mut variants = vec![];
+        for (_, child) in Children::new(node.children.iter().copied(), self.arena) {
+            if child.is(Nodes::EnumName) {
+                name = Some(self.input[child.span].to_string());
+            } else if child.is(Nodes::EnumVariant) {
+                variants.push(self.variant_decl(child)?);
+            }
+        }
+        Some(Value::Enum(Arc::new(EnumDecl { name, variants })))
+    }
+
+    fn variant_decl(&self, node: &Node) -> Option<VariantDecl> {
+        let mut children = Children::new(node.children.iter().copied(), self.arena);
+        let (_, name) = children.find_node(Nodes::Key)?;
+        let fields = children
+            .filter(|(_, child)| child.is(Nodes::EnumField))
+            .map(|(_, child)| self.input[child.span].to_string())
+            .collect();
+        Some(VariantDecl {
+            name: self.input[name.span].to_string(),
+            fields,
+        })
+    }
+
+    pub(crate) fn eval_variant(
+        &mut self,
+        decl: Arc<EnumDecl>,
+        id: NodeId,
+        node: &Node,
+    ) -> Option<Value> {
+        let input = self.input;
+        let mut args = vec![];
+        let (name_id, name) = if node.is(Nodes::Call) {
+            let mut children = Children::new(node.children.iter().copied(), self.arena);
+            let (name_id, name) = children.find_node(Nodes::Identifier)?;
+            while let Some((arg_id, _)) = children.find_node(Nodes::Value) {
+                args.push(self.eager_eval(arg_id, true)?);
+            }
+            (name_id, &input[name.span])
+        } else if node.is(Nodes::Identifier) {
+            (id, &input[node.span])
+        } else {
+            return None;
+        };
+
+        let expected = decl.variant(name).map(|variant| variant.fields.len());
+        let expected = self.expect_some(name_id, expected, Error::UnknownVariant(name.into()))?;
+        if expected != args.len() {
+            let error = Error::WrongFieldCount {
+                name: name.into(),
+                expected,
+                found: args.len(),
+            };
+            return self.expect_some(name_id, None, error);
+        }
+
+        Some(Value::Variant(Variant {
+            decl,
+            tag: name.into(),
+            fields: args,
+        }))
+    }
+
+    pub(crate) fn eval_match(&mut self, id: NodeId, node: &Node) -> Option<Value> {
+        let mut children = Children::new(node.children.iter().copied(), self.arena);
+        let (value_id, _) = children.find_node(Nodes::Value)?;
+        let value = self.eager_eval(value_id, true)?;
+
+        let mut arms = vec![];
+        for (_, arm) in children.filter(|(_, child)| child.is(Nodes::MatchArm)) {
+            let mut arm_children = Children::new(arm.children.iter().copied(), self.arena);
+            let (pattern_id, pattern) = arm_children.find_node(Nodes::Pattern)?;
+            let (body_id, _) = arm_children.find_node(Nodes::Value)?;
+            arms.push((pattern_id, self.read_pattern(pattern, &value), body_id));
+        }
+
+        self.check_arms(id, &value, &arms);
+
+        for (_, pattern, body_id) in &arms {
+            if let Some(bindings) = self.match_pattern(pattern, &value) {
+                let depth = self.locals.len();
+                self.locals.extend(bindings);
+                let result = self.eager_eval(*body_id, true);
+                self.locals.truncate(depth);
+                return result;
+            }
+        }
+
+        if self.errors.get(id).is_none() {
+            self.report(id, Error::NoMatchingArm(value.to_string()));
+        }
+        None
+    }
+
+    pub(crate) fn eval_let(&mut self, node: &Node) -> Option<Value> {
+        let mut children = Children::new(node.children.iter().copied(), self.arena);
+        let (pattern_id, _) = children.find(|(_, child)| is_let_pattern(child))?;
+        let (value_id, _) = children.find_node(Nodes::Value)?;
+        let (body_id, _) = children.find_node(Nodes::Value)?;
+
+        let value = self.eager_eval(value_id, true)?;
+        let mut bindings = vec![];
+        self.destructure(pattern_id, value, &mut bindings)?;
+
+        let depth = self.locals.len();
+        self.locals.extend(bindings);
+        let result = self.eager_eval(body_id, true);
+        self.locals.truncate(depth);
+        result
+    }
+
+    fn destructure(
+        &mut self,
+        id: NodeId,
+        value: Value,
+        bindings: &mut Vec<(String, Value)>,
+    ) -> Option<()> {
+        let node = self.arena.get(id);
+        if node.is(Nodes::PatternBinding) {
+            bindings.push((self.input[node.span].to_string(), value));
+            return Some(());
+        }
+        if !node.is(Nodes::PatternTuple) {
+            return Some(());
+        }
+
+        let patterns = Children::new(node.children.iter().copied(), self.arena)
+            .filter(|(_, child)| is_let_pattern(child))
+            .map(|(pattern_id, _)| pattern_id)
+            .collect::<Vec<_>>();
+        let values = match value {
+            Value::Tuple(values) => values,
+            _ => return self.expect_some(id, None, Error::ValueNotTuple),
+        };
+        if patterns.len() != values.len() {
+            let error = Error::TupleArityMismatch {
+                expected: patterns.len(),
+                found: values.len(),
+            };
+            return self.expect_some(id, None, error);
+        }
+        for (pattern_id, value) in patterns.into_iter().zip(values) {
+            self.destructure(pattern_id, value, bindings)?;
+        }
+        Some(())
+    }
+
+    // A bare name binds the value unless it is one of the value's variants.
+    fn read_pattern(&self, node: &Node, value: &Value) -> Pattern {
+        let mut path = vec![];
+        let mut bindings = vec![];
+        let mut parens = false;
+        for (child_id, child) in Children::new(node.children.iter().copied(), self.arena) {
+            let text = &self.input[child.span];
+            if child.is(Nodes::Token) && text == "(" {
+                parens = true;
+            } else if child.is(Nodes::PatternLiteral) {
+                return Pattern::Literal(child_id);
+            } else if child.is(Nodes::PatternPath) {
+                path.push(text.to_string());
+            } else if child.is(Nodes::PatternBinding) {
+                bindings.push(Some(text.to_string()));
+            } else if child.is(Nodes::Wildcard) {
+                if path.is_empty() {
+                    return Pattern::Wildcard;
+                }
+                bindings.push(None);
+            }
+        }
+        let variant = match (value, path.first()) {
+            (Value::Variant(variant), Some(name)) => variant.decl.variant(name).is_some(),
+            _ => false,
+        };
+        if path.len() == 1 && !parens && !variant {
+            return Pattern::Binding(path.remove(0));
+        }
+        Pattern::Variant { path, bindings }
+    }
+
+    fn match_pattern(&mut self, pattern: &Pattern, value: &Value) -> Option<Vec<(String, Value)>> {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => Some(vec![]),
+            (Pattern::Binding(name), value) => Some(vec![(name.clone(), value.clone())]),
+            (Pattern::Literal(id), value) => {
+                let literal = self.eval(*id)?;
+                if &literal == value {
+                    Some(vec![])
+                } else {
+                    None
+                }
+            }
+            (Pattern::Variant { path, bindings }, Value::Variant(variant))
+                if path.last() == Some(&variant.tag) && bindings.len() == variant.fields.len() =>
+            {
+                Some(
+                    bindings
+                        .iter()
+                        .zip(variant.fields.iter())
+                        .filter_map(|(binding, field)| {
+                            binding.as_ref().map(|name| (name.clone(), field.clone()))
+                        })
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn check_arms(&mut self, id: NodeId, value: &Value, arms: &[(NodeId, Pattern, NodeId)]) {
+        let decl = match value {
+            Value::Variant(variant) => Some(variant.decl.clone()),
+            _ => None,
+        };
+        let mut covered: Vec<&str> = vec![];
+        let mut literals = vec![];
+        let mut exhaustive = false;
+
+        for (pattern_id, pattern, _) in arms {
+            if exhaustive {
+                self.report(*pattern_id, Error::UnreachableArm);
+                continue;
+            }
+            match pattern {
+                Pattern::Wildcard | Pattern::Binding(_) => exhaustive = true,
+                Pattern::Literal(literal_id) => {
+                    if let Some(literal) = self.eval(*literal_id) {
+                        if literals.contains(&literal) {
+                            self.report(*pattern_id, Error::UnreachableArm);
+                        } else {
+                            literals.push(literal);
+                        }
+                    }
+                }
+                Pattern::Variant { path, bindings } => {
+                    let (decl, tag) = match (&decl, path.last()) {
+                        (Some(decl), Some(tag)) => (decl, tag),
+                        _ => continue,
+                    };
+                    let prefix = path.iter().rev().nth(1);
+                    if prefix.is_some() && decl.name.is_some() && decl.name.as_ref() != prefix {
+                        self.report(*pattern_id, Error::UnknownVariant(path.join(".")));
+                        continue;
+                    }
+                    match decl.variant(tag) {
+                        None => self.report(*pattern_id, Error::UnknownVariant(tag.clone())),
+                        Some(variant) if variant.fields.len() != bindings.len() => {
+                            let error = Error::WrongFieldCount {
+                                name: tag.clone(),
+                                expected: variant.fields.len(),
+                                found: bindings.len(),
+                            };
+                            self.report(*pattern_id, error);
+                        }
+                        Some(_) if covered.contains(&tag.as_str()) => {
+                            self.report(*pattern_id, Error::UnreachableArm)
+                        }
+                        Some(_) => covered.push(tag),
+                    }
+                }
+            }
+        }
+
+        if exhaustive {
+            return;
+        }
+        let missing = match (&decl, value) {
+            (Some(decl), _) => decl
+                .variants
+                .iter()
+                .filter(|variant| !covered.contains(&variant.name.as_str()))
+                .map(|variant| format!("`{}`", variant.name))
+                .collect::<Vec<_>>(),
+            (None, Value::Boolean(_)) => [true, false]
+                .iter()
+                .filter(|b| !literals.contains(&Value::Boolean(**b)))
+                .map(|b| format!("`{}`", b))
+                .collect(),
+            _ => vec!["`_`".into()],
+        };
+        if !missing.is_empty() {
+            self.report(id, Error::NonExhaustiveMatch(missing.join(", ")));
+        }
+    }
+}
+
+fn is_let_pattern(node: &Node) -> bool {
+    node.is(Nodes::PatternBinding) || node.is(Nodes::PatternTuple) || node.is(Nodes::Wildcard)
+}
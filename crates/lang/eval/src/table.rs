@@ -0,0 +1,270 @@
+use crate::{MdKind, MdNode, Value};
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableEntry {
+    pub from: u32,
+    pub to: u32,
+    pub value: Value,
+}
+
+impl TableEntry {
+    pub fn range(&self) -> String {
+        if self.from == self.to {
+            self.from.to_string()
+        } else {
+            format!("{}-{}", self.from, self.to)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub entries: Vec<TableEntry>,
+}
+
+impl Table {
+    pub fn new(mut entries: Vec<TableEntry>) -> Result<Self, String> {
+        if entries.is_empty() {
+            return Err("a table needs at least one entry".into());
+        }
+        entries.sort_by_key(|entry| entry.from);
+        let mut next = 1;
+        for entry in &entries {
+            if entry.from > entry.to {
+                return Err(format!("range {}-{} is empty", entry.from, entry.to));
+            }
+            if entry.from < next {
+                return Err(format!("roll {} is covered twice", entry.from));
+            }
+            if entry.from > next {
+                return Err(format!("roll {} is not covered", next));
+            }
+            next = entry
+                .to
+                .checked_add(1)
+                .ok_or_else(|| format!("range {} is too large", entry.range()))?;
+        }
+        Ok(Self { entries })
+    }
+
+    // `[(1, "goblins"), ("2-5", "orcs")]` or a markdown list with `1-3:` prefixed items.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let entries = match value {
+            Value::Array(rows) => rows
+                .iter()
+                .map(|row| match row {
+                    Value::Tuple(pair) if pair.len() == 2 => {
+                        let (from, to) = match &pair[0] {
+                            Value::Number(n) if *n > 0 => {
+                                let roll = u32::try_from(*n)
+                                    .map_err(|_| format!("`{}` is not a range", n))?;
+                                (roll, roll)
+                            }
+                            Value::String(range) => parse_range(range)
+                                .ok_or_else(|| format!("`{}` is not a range", range))?,
+                            other => return Err(format!("`{}` is not a range", other)),
+                        };
+                        Ok(TableEntry {
+                            from,
+                            to,
+                            value: pair[1].clone(),
+                        })
+                    }
+                    other => Err(format!("expected `(range, value)`, found {}", other.kind())),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Value::Markdown(nodes) => {
+                let items = find_list(nodes).ok_or("expected a markdown list")?;
+                let mut next = 1;
+                items
+                    .iter()
+                    .map(|item| {
+                        let (range, children) = strip_range(&item.children);
+                        let (from, to) = range.unwrap_or((next, next));
+                        next = to.saturating_add(1);
+                        TableEntry {
+                            from,
+                            to,
+                            value: Value::Markdown(children),
+                        }
+                    })
+                    .collect()
+            }
+            other => return Err(format!("cannot make a table out of {}", other.kind())),
+        };
+        Self::new(entries)
+    }
+
+    pub fn die(&self) -> u32 {
+        self.entries
+            .last()
+            .map(|entry| entry.to)
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, roll: u32) -> Option<&TableEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.from <= roll && roll <= entry.to)
+    }
+
+    // The same seed always gives the same result, nested tables are rolled with the following numbers.
+    pub fn pick(&self, seed: u64) -> Value {
+        self.roll(&mut Rng::new(seed))
+    }
+
+    pub(crate) fn roll(&self, rng: &mut Rng) -> Value {
+        let roll = rng.roll(self.die());
+        let value = self
+            .get(roll)
+            .map(|entry| entry.value.clone())
+            .unwrap_or(Value::Null);
+        resolve(value, rng)
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "table[")?;
+        for (idx, entry) in self.entries.iter().enumerate() {
+            let sep = if idx == 0 { "" } else { ", " };
+            write!(f, "{}{}: {}", sep, entry.range(), entry.value)?;
+        }
+        write!(f, "]")
+    }
+}
+
+fn resolve(value: Value, rng: &mut Rng) -> Value {
+    match value {
+        Value::Table(table) => table.roll(rng),
+        Value::Markdown(nodes) => Value::Markdown(
+            nodes
+                .into_iter()
+                .map(|node| resolve_md(node, rng))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn resolve_md(node: MdNode, rng: &mut Rng) -> MdNode {
+    let kind = match node.kind {
+        MdKind::Value(value) | MdKind::Code(value) if matches!(*value, Value::Table(_)) => {
+            MdKind::Value(Box::new(resolve(*value, rng)))
+        }
+        kind => kind,
+    };
+    MdNode {
+        kind,
+        children: node
+            .children
+            .into_iter()
+            .map(|child| resolve_md(child, rng))
+            .collect(),
+    }
+}
+
+fn find_list(nodes: &[MdNode]) -> Option<&[MdNode]> {
+    nodes.iter().find_map(|node| match node.kind {
+        MdKind::UnorderedList | MdKind::OrderedList => Some(&node.children[..]),
+        _ => find_list(&node.children),
+    })
+}
+
+fn parse_range(text: &str) -> Option<(u32, u32)> {
+    let mut parts = text.trim().splitn(2, '-');
+    let from = parts.next()?.trim().parse().ok()?;
+    let to = match parts.next() {
+        Some(to) => to.trim().parse().ok()?,
+        None => from,
+    };
+    if from == 0 {
+        return None;
+    }
+    Some((from, to))
+}
+
+// Takes the `1-3:` prefix off the first text of a list item.
+fn strip_range(children: &[MdNode]) -> (Option<(u32, u32)>, Vec<MdNode>) {
+    let mut children = children.to_vec();
+    let first = match children.first_mut() {
+        Some(MdNode {
+            kind: MdKind::Paragraph,
+            children,
+        }) => children.first_mut(),
+        first => first,
+    };
+    if let Some(MdNode {
+        kind: MdKind::Text(text),
+        ..
+    }) = first
+    {
+        if let Some(colon) = text.find(':') {
+            if let Some(range) = parse_range(&text[..colon]) {
+                *text = text[colon + 1..].trim_start().to_string();
+                return (Some(range), children);
+            }
+        }
+    }
+    (None, children)
+}
+
+// splitmix64, good enough for dice and stable across platforms.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    // Seeded from the clock, for rolls and ids made outside of evaluation.
+    pub fn from_time() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            hasher.write_u128(time.as_nanos());
+        }
+        Self(hasher.finish())
+    }
+
+    // Seeded from where a roll happens, the same key always gives the same rolls.
+    pub fn from_key(key: impl Hash) -> Self {
+        let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+        key.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // From 1 to `die`.
+    pub fn roll(&mut self, die: u32) -> u32 {
+        (self.next_u64() % u64::from(die.max(1))) as u32 + 1
+    }
+}
+
+// FNV-1a, unlike `DefaultHasher` it doesn't change between releases.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
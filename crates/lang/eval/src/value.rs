@@ -1,14 +1,57 @@
+use crate::{Date, MdNode, Table};
+use indexmap::IndexMap;
 use neu_parser::NodeId;
-use std::collections::BTreeMap;
 use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumDecl {
+    pub name: Option<String>,
+    pub variants: Vec<VariantDecl>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantDecl {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub decl: Arc<EnumDecl>,
+    pub tag: String,
+    pub fields: Vec<Value>,
+}
+
+impl EnumDecl {
+    pub fn variant(&self, tag: &str) -> Option<&VariantDecl> {
+        self.variants.iter().find(|variant| variant.name == tag)
+    }
+}
+
+impl Variant {
+    pub fn field_names(&self) -> &[String] {
+        self.decl
+            .variant(&self.tag)
+            .map(|variant| &variant.fields[..])
+            .unwrap_or_default()
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Value {
     Number(i64),
+    Date(Date),
     Boolean(bool),
+    Null,
     String(String),
     Array(Vec<Value>),
-    Struct(BTreeMap<String, Value>),
+    Tuple(Vec<Value>),
+    Struct(IndexMap<String, Value>),
+    Enum(Arc<EnumDecl>),
+    Variant(Variant),
+    Markdown(Vec<MdNode>),
+    Table(Table),
 
     Lazy { id: NodeId },
 }
@@ -21,12 +64,41 @@ impl Value {
         }
     }
 
-    pub fn into_struct(self) -> Option<BTreeMap<String, Value>> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn into_struct(self) -> Option<IndexMap<String, Value>> {
         match self {
             Self::Struct(s) => Some(s),
             _ => None,
         }
     }
+
+    pub fn into_tuple(self) -> Option<Vec<Value>> {
+        match self {
+            Self::Tuple(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Number(_) => "number",
+            Self::Date(_) => "date",
+            Self::Boolean(_) => "boolean",
+            Self::Null => "null",
+            Self::String(_) => "string",
+            Self::Array(_) => "array",
+            Self::Tuple(_) => "tuple",
+            Self::Struct(_) => "struct",
+            Self::Enum(_) => "enum",
+            Self::Variant(_) => "variant",
+            Self::Markdown(_) => "markdown",
+            Self::Table(_) => "table",
+            Self::Lazy { .. } => "lazy",
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -36,7 +108,9 @@ impl fmt::Display for Value {
             Self::Lazy { id, .. } => write!(f, "{:?}", id),
 
             Self::Number(n) => write!(f, "{}", n),
+            Self::Date(date) => write!(f, "@{}", date),
             Self::Boolean(b) => write!(f, "{}", b),
+            Self::Null => write!(f, "null"),
             Self::String(s) => write!(f, "{:?}", s),
             Self::Array(a) => {
                 write!(f, "[")?;
@@ -51,6 +125,59 @@ impl fmt::Display for Value {
                 }
                 write!(f, "]")
             }
+            Self::Tuple(t) => {
+                write!(f, "(")?;
+                for (idx, v) in t.iter().enumerate() {
+                    let sep = if idx == 0 { "" } else { ", " };
+                    write!(f, "{}{}", sep, v)?;
+                }
+                if t.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Self::Enum(decl) => {
+                write!(f, "enum ")?;
+                if let Some(name) = &decl.name {
+                    write!(f, "{} ", name)?;
+                }
+                write!(f, "{{")?;
+                for (idx, variant) in decl.variants.iter().enumerate() {
+                    let sep = if idx == 0 { " " } else { ", " };
+                    write!(f, "{}{}", sep, variant.name)?;
+                    if !variant.fields.is_empty() {
+                        write!(f, "({})", variant.fields.join(", "))?;
+                    }
+                }
+                if !decl.variants.is_empty() {
+                    write!(f, " ")?;
+                }
+                write!(f, "}}")
+            }
+            Self::Variant(variant) => {
+                if let Some(name) = &variant.decl.name {
+                    write!(f, "{}.", name)?;
+                }
+                write!(f, "{}", variant.tag)?;
+                if !variant.fields.is_empty() {
+                    write!(f, "(")?;
+                    for (idx, v) in variant.fields.iter().enumerate() {
+                        let sep = if idx == 0 { "" } else { ", " };
+                        write!(f, "{}{}", sep, v)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            Self::Table(table) => write!(f, "{}", table),
+            Self::Markdown(nodes) => {
+                write!(f, "md[")?;
+                for (idx, node) in nodes.iter().enumerate() {
+                    let sep = if idx == 0 { "" } else { ", " };
+                    write!(f, "{}{}", sep, node)?;
+                }
+                write!(f, "]")
+            }
             Self::Struct(s) => {
                 if f.alternate() {
                     writeln!(f, "{{")?;
@@ -1,18 +1,41 @@
 #![allow(dead_code)]
-use crate::_render;
+use crate::graph::{EdgeKind, Graph, GraphEdge, GraphNode};
+use crate::html::HtmlPolicy;
 use crate::result::RenderResult;
+use crate::secret::{Audience, Secret};
+use crate::timeline::TimelineEntry;
+use crate::{_render, body_text, plain_text};
+use indexmap::IndexMap;
 use neu_analyze::db::Analyzer;
+use neu_analyze::Mention;
 use neu_canceled::Canceled;
 use neu_eval::db::Evaluator;
+use neu_eval::Value;
+use neu_parser::NodeId;
 use neu_syntax::ast::{ArticleItem, RootAst};
 use neu_syntax::db::{FileId, Parser};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
 #[salsa::query_group(RendererDatabase)]
 pub trait Renderer: salsa::Database + Parser + Analyzer + Evaluator {
+    #[salsa::input]
+    fn html_policy(&self) -> HtmlPolicy;
+    #[salsa::input]
+    fn audience(&self) -> Audience;
+
     fn render_md(&self, path: FileId) -> Arc<RenderResult>;
     fn render_item(&self, kind: String, id: String) -> Arc<RenderResult>;
     fn render_ast(&self, path: FileId, article_item: ArticleItem) -> Arc<RenderResult>;
+    fn article_text(&self, path: FileId, article_item: ArticleItem) -> Arc<String>;
+
+    fn secret_articles(&self) -> Arc<HashSet<(String, String)>>;
+    fn is_hidden(&self, kind: String, id: String) -> bool;
+    fn visible_mention_nodes(&self) -> Arc<Vec<(FileId, NodeId, Mention)>>;
+
+    fn article_parents(&self) -> Arc<HashMap<(String, String), (String, String)>>;
+    fn article_graph(&self) -> Arc<Graph>;
+    fn timeline(&self) -> Arc<Vec<TimelineEntry>>;
 }
 
 fn render_md(db: &dyn Renderer, path: FileId) -> Arc<RenderResult> {
@@ -30,11 +53,21 @@ fn render_ast(db: &dyn Renderer, path: FileId, article_item: ArticleItem) -> Arc
 
     let mut result = RenderResult::default();
 
-    _render(db, path, article_item, &parsed, &mut result);
+    result.parts = _render(db, path, article_item, &parsed, &mut result);
 
     Arc::new(result)
 }
 
+fn article_text(db: &dyn Renderer, path: FileId, article_item: ArticleItem) -> Arc<String> {
+    Canceled::cancel_if(db.salsa_runtime());
+    let parsed = db.parse_syntax(path);
+    let mut text = String::new();
+    if let Some(body) = article_item.body {
+        body_text(db, path, body, &parsed, &mut text);
+    }
+    Arc::new(text)
+}
+
 fn render_item(db: &dyn Renderer, kind: String, id: String) -> Arc<RenderResult> {
     Canceled::cancel_if(db.salsa_runtime());
     let article = db.find_md(kind.clone(), id.clone());
@@ -51,3 +84,186 @@ fn render_item(db: &dyn Renderer, kind: String, id: String) -> Arc<RenderResult>
 
     db.render_ast(path, article_item)
 }
+
+fn secret_articles(db: &dyn Renderer) -> Arc<HashSet<(String, String)>> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let mut secret = HashSet::new();
+    for (kind, id, path, article_item) in db.parse_all_mds() {
+        let fields = fields(db, path, &article_item);
+        let field = fields.as_ref().and_then(|fields| fields.get("secret"));
+        if Secret::from_value(field) == Some(Secret::Article) {
+            secret.insert((kind, id));
+        }
+    }
+
+    // Nested items are secret when written in a secret block or inside a secret article.
+    let nested = db
+        .all_mention_nodes()
+        .into_iter()
+        .filter(|(path, node, _)| {
+            EdgeKind::of(db.parse_syntax(*path).arena.get(*node)) == EdgeKind::Nested
+        })
+        .map(|(_, _, mention)| {
+            let parent = (mention.orig_kind, mention.orig_id);
+            (mention.secret, parent, (mention.kind, mention.id))
+        })
+        .collect::<Vec<_>>();
+    loop {
+        let mut changed = false;
+        for (in_block, parent, item) in &nested {
+            if (*in_block || secret.contains(parent)) && secret.insert(item.clone()) {
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    Arc::new(secret)
+}
+
+fn is_hidden(db: &dyn Renderer, kind: String, id: String) -> bool {
+    Canceled::cancel_if(db.salsa_runtime());
+    db.audience() == Audience::Player && db.secret_articles().contains(&(kind, id))
+}
+
+// Players don't see secret mentions, nor mentions from or to secret articles.
+fn visible_mention_nodes(db: &dyn Renderer) -> Arc<Vec<(FileId, NodeId, Mention)>> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let mentions = db.all_mention_nodes();
+    if db.audience() == Audience::Gm {
+        return Arc::new(mentions);
+    }
+    let secret = db.secret_articles();
+    let visible = mentions
+        .into_iter()
+        .filter(|(_, _, mention)| {
+            let orig = (mention.orig_kind.clone(), mention.orig_id.clone());
+            let target = (mention.kind.clone(), mention.id.clone());
+            !mention.secret && !secret.contains(&orig) && !secret.contains(&target)
+        })
+        .collect();
+    Arc::new(visible)
+}
+
+// Nested `++ kind:id ++` items point to the article they were written in.
+fn article_parents(db: &dyn Renderer) -> Arc<HashMap<(String, String), (String, String)>> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let mut parents = HashMap::new();
+    for (path, node, mention) in db.all_mention_nodes() {
+        if EdgeKind::of(db.parse_syntax(path).arena.get(node)) == EdgeKind::Nested {
+            let parent = (mention.orig_kind, mention.orig_id);
+            parents.insert((mention.kind, mention.id), parent);
+        }
+    }
+    Arc::new(parents)
+}
+
+fn article_graph(db: &dyn Renderer) -> Arc<Graph> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let nodes = db
+        .parse_all_mds()
+        .into_iter()
+        .filter(|(kind, id, _, _)| !db.is_hidden(kind.clone(), id.clone()))
+        .map(|(kind, id, path, article_item)| GraphNode {
+            kind,
+            id,
+            title: title(&fields(db, path, &article_item)),
+            file: db.lookup_file_id(path).0,
+        })
+        .collect();
+
+    let edges = db
+        .visible_mention_nodes()
+        .iter()
+        .map(|(path, node, mention)| GraphEdge {
+            from: format!("{}:{}", mention.orig_kind, mention.orig_id),
+            to: format!("{}:{}", mention.kind, mention.id),
+            via: EdgeKind::of(db.parse_syntax(*path).arena.get(*node)),
+            anchor: mention.anchor.clone(),
+            snippet: mention.snippet.clone(),
+        })
+        .collect::<BTreeSet<_>>();
+
+    Arc::new(Graph::new(nodes, edges))
+}
+
+fn timeline(db: &dyn Renderer) -> Arc<Vec<TimelineEntry>> {
+    Canceled::cancel_if(db.salsa_runtime());
+
+    let parents = db.article_parents();
+
+    let mut articles = HashMap::new();
+    for (kind, id, path, article_item) in db.parse_all_mds() {
+        articles.entry((kind, id)).or_insert_with(|| {
+            let evaluated = fields(db, path, &article_item);
+            (path, article_item, evaluated)
+        });
+    }
+
+    let mut entries = vec![];
+    for ((kind, id), (path, article_item, fields)) in &articles {
+        if db.is_hidden(kind.clone(), id.clone()) {
+            continue;
+        }
+        let date = match fields.as_ref().and_then(|fields| fields.get("date")) {
+            Some(Value::Date(date)) => *date,
+            _ => continue,
+        };
+
+        // Nested items are grouped under the top level article they were written in.
+        let mut top = (kind.clone(), id.clone());
+        let mut depth = 0;
+        while let Some(parent) = parents.get(&top) {
+            depth += 1;
+            if depth > parents.len() {
+                break;
+            }
+            top = parent.clone();
+        }
+        let session = if depth > 0 {
+            let session_title = articles
+                .get(&top)
+                .map(|(_, _, fields)| title(fields))
+                .unwrap_or_else(|| "???".into());
+            Some((top.0, top.1, session_title))
+        } else {
+            None
+        };
+
+        entries.push(TimelineEntry {
+            kind: kind.clone(),
+            id: id.clone(),
+            title: title(fields),
+            date,
+            session,
+            file: *path,
+            node: article_item.item_id.unwrap_or(article_item.id),
+        });
+    }
+    entries.sort_by(|a, b| (a.date, &a.kind, &a.id).cmp(&(b.date, &b.kind, &b.id)));
+
+    Arc::new(entries)
+}
+
+fn fields(
+    db: &dyn Renderer,
+    path: FileId,
+    article_item: &ArticleItem,
+) -> Option<IndexMap<String, Value>> {
+    let strukt = article_item.strukt?;
+    db.eval(path, strukt).value.clone()?.into_struct()
+}
+
+fn title(fields: &Option<IndexMap<String, Value>>) -> String {
+    fields
+        .as_ref()
+        .and_then(|fields| fields.get("title"))
+        .map(plain_text)
+        .unwrap_or_else(|| "???".into())
+}
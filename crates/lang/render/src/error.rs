@@ -0,0 +1,33 @@
+use derive_more::Display;
+
+#[derive(Debug, Display)]
+pub enum Error {
+    #[display(fmt = "Raw HTML was stripped: `{}`", _0)]
+    HtmlStripped(String),
+
+    #[display(fmt = "Disallowed HTML was removed from `{}`", _0)]
+    HtmlSanitized(String),
+
+    #[display(fmt = "`layout` should be a struct of field layouts")]
+    LayoutNotStruct,
+
+    #[display(
+        fmt = "Unknown layout `{}` for field `{}`, expected `auto`, `inline`, `list`, `table` or `fields`",
+        hint,
+        field
+    )]
+    UnknownLayout { field: String, hint: String },
+
+    #[display(fmt = "`secret` should be `true` or a list of field names")]
+    SecretNotFields,
+
+    #[display(fmt = "Couldn't find article {}:{}", kind, id)]
+    ArticleNotFound { kind: String, id: String },
+
+    #[display(fmt = "Couldn't find section `{}` in {}:{}", section, kind, id)]
+    SectionNotFound {
+        kind: String,
+        id: String,
+        section: String,
+    },
+}
@@ -0,0 +1,287 @@
+use crate::html::escape;
+use neu_parser::Node;
+use neu_syntax::Nodes;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Nested,
+    Ref,
+    Link,
+}
+
+impl EdgeKind {
+    pub(crate) fn of(node: &Node) -> Self {
+        if node.is(Nodes::ArticleItem) {
+            Self::Nested
+        } else if node.is(Nodes::ArticleRef) {
+            Self::Ref
+        } else {
+            Self::Link
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nested => "nested",
+            Self::Ref => "ref",
+            Self::Link => "link",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+    GraphMl,
+}
+
+impl Default for GraphFormat {
+    fn default() -> Self {
+        Self::Dot
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            "graphml" => Ok(Self::GraphMl),
+            _ => Err(format!(
+                "Unknown graph format `{}`, expected `dot`, `json` or `graphml`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GraphNode {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub file: String,
+}
+
+impl GraphNode {
+    fn key(&self) -> String {
+        format!("{}:{}", self.kind, self.id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub via: EdgeKind,
+    pub anchor: Option<String>,
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl Graph {
+    pub(crate) fn new(nodes: Vec<GraphNode>, edges: BTreeSet<GraphEdge>) -> Self {
+        let mut keys = HashSet::new();
+        let nodes = nodes
+            .into_iter()
+            .filter(|node| keys.insert(node.key()))
+            .collect::<Vec<_>>();
+        // Unresolved links are reported by the diagnostician, the graph only keeps known articles.
+        // Edges are sorted, so repeated mentions keep the first one: nested, then ref, then link.
+        let mut pairs = HashSet::new();
+        let edges = edges
+            .into_iter()
+            .filter(|edge| keys.contains(&edge.from) && keys.contains(&edge.to))
+            .filter(|edge| pairs.insert((edge.from.clone(), edge.to.clone())))
+            .collect();
+        Self { nodes, edges }
+    }
+
+    pub fn contains(&self, kind: &str, id: &str) -> bool {
+        self.nodes
+            .iter()
+            .any(|node| node.kind == kind && node.id == id)
+    }
+
+    pub fn filter(
+        &self,
+        kinds: &[String],
+        root: Option<(&str, &str)>,
+        depth: Option<usize>,
+    ) -> Self {
+        let mut keys = self
+            .nodes
+            .iter()
+            .filter(|node| kinds.is_empty() || kinds.contains(&node.kind))
+            .map(GraphNode::key)
+            .collect::<HashSet<_>>();
+
+        if let Some((kind, id)) = root {
+            let root = format!("{}:{}", kind, id);
+            // Links are followed both ways, so `depth` counts articles mentioning the root too.
+            let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+            for edge in &self.edges {
+                if keys.contains(&edge.from) && keys.contains(&edge.to) {
+                    neighbours.entry(&edge.from).or_default().push(&edge.to);
+                    neighbours.entry(&edge.to).or_default().push(&edge.from);
+                }
+            }
+
+            let mut reached = HashSet::new();
+            let mut queue = VecDeque::new();
+            if keys.contains(&root) {
+                reached.insert(root.clone());
+                queue.push_back((root.as_str(), 0));
+            }
+            while let Some((key, distance)) = queue.pop_front() {
+                if depth.map(|depth| distance >= depth).unwrap_or(false) {
+                    continue;
+                }
+                for next in neighbours.get(key).into_iter().flatten() {
+                    if reached.insert(next.to_string()) {
+                        queue.push_back((*next, distance + 1));
+                    }
+                }
+            }
+            keys = reached;
+        }
+
+        Self {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| keys.contains(&node.key()))
+                .cloned()
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|edge| keys.contains(&edge.from) && keys.contains(&edge.to))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn export(&self, format: GraphFormat) -> String {
+        let mut output = String::new();
+        match format {
+            GraphFormat::Dot => self.write_dot(&mut output),
+            GraphFormat::Json => self.write_json(&mut output),
+            GraphFormat::GraphMl => self.write_graphml(&mut output),
+        }
+        .expect("Writing to string");
+        output
+    }
+
+    fn write_dot(&self, w: &mut impl Write) -> std::fmt::Result {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        writeln!(w, "digraph articles {{")?;
+        for node in &self.nodes {
+            writeln!(
+                w,
+                "    {} [label={}, kind={}, file={}];",
+                quote(&node.key()),
+                quote(&node.title),
+                quote(&node.kind),
+                quote(&node.file)
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                w,
+                "    {} -> {} [via={}];",
+                quote(&edge.from),
+                quote(&edge.to),
+                quote(edge.via.name())
+            )?;
+        }
+        writeln!(w, "}}")
+    }
+
+    fn write_json(&self, w: &mut impl Write) -> std::fmt::Result {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| JsonNode {
+                id: node.key(),
+                kind: &node.kind,
+                title: &node.title,
+                file: &node.file,
+            })
+            .collect();
+        let graph = JsonGraph {
+            nodes,
+            edges: &self.edges,
+        };
+        let json = serde_json::to_string(&graph).map_err(|_| std::fmt::Error)?;
+        writeln!(w, "{}", json)
+    }
+
+    fn write_graphml(&self, w: &mut impl Write) -> std::fmt::Result {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for key in &["kind", "title", "file"] {
+            writeln!(
+                w,
+                r#"  <key id="{key}" for="node" attr.name="{key}" attr.type="string"/>"#,
+                key = key
+            )?;
+        }
+        writeln!(
+            w,
+            r#"  <key id="via" for="edge" attr.name="via" attr.type="string"/>"#
+        )?;
+        writeln!(w, r#"  <graph id="articles" edgedefault="directed">"#)?;
+        for node in &self.nodes {
+            writeln!(
+                w,
+                r#"    <node id="{}"><data key="kind">{}</data><data key="title">{}</data><data key="file">{}</data></node>"#,
+                escape(&node.key()),
+                escape(&node.kind),
+                escape(&node.title),
+                escape(&node.file)
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                w,
+                r#"    <edge source="{}" target="{}"><data key="via">{}</data></edge>"#,
+                escape(&edge.from),
+                escape(&edge.to),
+                edge.via.name()
+            )?;
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")
+    }
+}
+
+#[derive(Serialize)]
+struct JsonGraph<'g> {
+    nodes: Vec<JsonNode<'g>>,
+    edges: &'g [GraphEdge],
+}
+
+#[derive(Serialize)]
+struct JsonNode<'g> {
+    id: String,
+    kind: &'g str,
+    title: &'g str,
+    file: &'g str,
+}
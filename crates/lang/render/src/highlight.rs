@@ -0,0 +1,248 @@
+use crate::html::escape;
+use neu_syntax::lexers::neu::{Lexer, Token};
+
+struct Language {
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+const RUST: Language = Language {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    literals: &["true", "false", "None", "Some", "Ok", "Err"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+};
+
+const JAVASCRIPT: Language = Language {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "let",
+        "new",
+        "of",
+        "return",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    literals: &["true", "false", "null", "undefined"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+};
+
+const PYTHON: Language = Language {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield",
+    ],
+    literals: &["True", "False", "None"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const SHELL: Language = Language {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "until", "while",
+    ],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const TOML: Language = Language {
+    keywords: &[],
+    literals: &["true", "false"],
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+};
+
+const JSON: Language = Language {
+    keywords: &[],
+    literals: &["true", "false", "null"],
+    line_comments: &[],
+    block_comment: None,
+    quotes: &['"'],
+};
+
+fn language(name: &str) -> Option<&'static Language> {
+    match name.to_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "javascript" | "js" | "typescript" | "ts" => Some(&JAVASCRIPT),
+        "python" | "py" => Some(&PYTHON),
+        "shell" | "sh" | "bash" => Some(&SHELL),
+        "toml" => Some(&TOML),
+        "json" => Some(&JSON),
+        _ => None,
+    }
+}
+
+pub(crate) fn highlight(lang: &str, code: &str) -> Option<String> {
+    if lang == "neu" {
+        return Some(highlight_neu(code));
+    }
+    language(lang).map(|language| highlight_with(language, code))
+}
+
+fn span(output: &mut String, class: &str, text: &str) {
+    output.push_str(&format!(
+        r#"<span class="hl-{}">{}</span>"#,
+        class,
+        escape(text)
+    ));
+}
+
+fn highlight_with(language: &Language, code: &str) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        let block_comment = language
+            .block_comment
+            .filter(|(open, _)| rest.starts_with(open));
+
+        let len = if language
+            .line_comments
+            .iter()
+            .any(|comment| rest.starts_with(comment))
+        {
+            let len = rest.find('\n').unwrap_or_else(|| rest.len());
+            span(&mut output, "comment", &rest[..len]);
+            len
+        } else if let Some((open, close)) = block_comment {
+            let len = rest[open.len()..]
+                .find(close)
+                .map(|end| open.len() + end + close.len())
+                .unwrap_or_else(|| rest.len());
+            span(&mut output, "comment", &rest[..len]);
+            len
+        } else if language.quotes.contains(&c) {
+            let len = string_len(rest, c);
+            span(&mut output, "string", &rest[..len]);
+            len
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_')
+                .unwrap_or_else(|| rest.len());
+            span(&mut output, "number", &rest[..len]);
+            len
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or_else(|| rest.len());
+            let word = &rest[..len];
+            if language.keywords.contains(&word) {
+                span(&mut output, "keyword", word);
+            } else if language.literals.contains(&word) {
+                span(&mut output, "literal", word);
+            } else {
+                output.push_str(&escape(word));
+            }
+            len
+        } else {
+            output.push_str(&escape(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    output
+}
+
+// Strings end at the closing quote or, unless they are template literals, at the end of the line.
+fn string_len(s: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (idx, c) in s.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return idx + c.len_utf8();
+        } else if c == '\n' && quote != '`' {
+            return idx;
+        }
+    }
+    s.len()
+}
+
+pub(crate) fn highlight_neu(code: &str) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut offset = 0;
+    'outer: while offset < code.len() {
+        let rest = &code[offset..];
+        let mut lexed = 0;
+        for token in Lexer::new(rest) {
+            let text = &rest[token.span];
+            lexed = usize::from(token.span.end());
+            let class = match token.kind {
+                // Strings have their own lexer, so the whole literal is highlighted at once.
+                Token::DoubleQuote | Token::MdQuote => {
+                    let end = lexed;
+                    let hashes = text.chars().filter(|c| *c == '#').count();
+                    let closing = format!("{:#<width$}", "\"", width = hashes + 1);
+                    let len = rest[end..]
+                        .find(&closing)
+                        .map(|idx| end + idx + closing.len())
+                        .unwrap_or_else(|| rest.len());
+                    let start = usize::from(token.span.start());
+                    span(&mut output, "string", &rest[start..len]);
+                    offset += len;
+                    continue 'outer;
+                }
+                Token::Comment => "comment",
+                Token::Number | Token::Date => "number",
+                Token::True | Token::False | Token::Null => "literal",
+                Token::Import | Token::As | Token::Enum | Token::Match | Token::Let | Token::In => {
+                    "keyword"
+                }
+                _ => {
+                    output.push_str(&escape(text));
+                    continue;
+                }
+            };
+            span(&mut output, class, text);
+        }
+        output.push_str(&escape(&rest[lexed..]));
+        break;
+    }
+    output
+}
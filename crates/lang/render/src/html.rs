@@ -1,25 +1,95 @@
-use neu_eval::Value;
+use crate::markdown;
+use neu_eval::{Table, Value};
 use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HtmlPolicy {
+    Allow,
+    Sanitize,
+    Strip,
+}
+
+impl Default for HtmlPolicy {
+    fn default() -> Self {
+        Self::Sanitize
+    }
+}
+
+impl FromStr for HtmlPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "sanitize" => Ok(Self::Sanitize),
+            "strip" => Ok(Self::Strip),
+            _ => Err(format!(
+                "Unknown html policy `{}`, expected `allow`, `sanitize` or `strip`",
+                s
+            )),
+        }
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layout {
+    Auto,
+    Inline,
+    List,
+    Table,
+    Fields,
+}
+
+impl Layout {
+    pub(crate) fn from_hint(hint: &str) -> Option<Self> {
+        match hint {
+            "auto" => Some(Self::Auto),
+            "inline" => Some(Self::Inline),
+            "list" => Some(Self::List),
+            "table" => Some(Self::Table),
+            "fields" => Some(Self::Fields),
+            _ => None,
+        }
+    }
+}
 
 pub(crate) struct HtmlValue<'v> {
     value: &'v Value,
+    policy: HtmlPolicy,
+    layout: Layout,
 }
 
-impl<'v> fmt::Display for HtmlValue<'v> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl<'v> HtmlValue<'v> {
+    fn nested(&self, value: &'v Value) -> Self {
+        render_value(value, self.policy)
+    }
+
+    fn write_inline(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let width = f.width().unwrap_or_default();
         match self.value {
-            Value::Number(n) => write!(f, "{}", n),
-            Value::Boolean(b) => write!(f, "{}", b),
-            Value::String(s) => write!(f, "{}", s),
             Value::Array(a) => {
                 write!(f, "[")?;
                 if !a.is_empty() {
                     if let Some(v) = a.iter().next() {
-                        write!(f, " {}", v)?;
+                        write!(f, " {}", escape(&v.to_string()))?;
                     }
                     for v in a.iter().skip(1) {
-                        write!(f, ", {}", v)?;
+                        write!(f, ", {}", escape(&v.to_string()))?;
                     }
                     write!(f, " ")?;
                 }
@@ -32,15 +102,200 @@ impl<'v> fmt::Display for HtmlValue<'v> {
                 }
                 let c_width = width + 4;
                 for (k, v) in s.iter() {
-                    writeln!(f, "{:width$}{} = {:#width$},", " ", k, v, width = c_width)?;
+                    let v = format!("{:#width$}", v, width = c_width);
+                    writeln!(
+                        f,
+                        "{:width$}{} = {},",
+                        " ",
+                        escape(k),
+                        escape(&v),
+                        width = c_width
+                    )?;
                 }
                 write!(f, "{:width$}}}", " ", width = width)
             }
-            Value::Lazy { .. } => unreachable!("lazy render"),
+            value => write!(f, "{}", self.nested(value)),
+        }
+    }
+
+    fn write_list<'i>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        items: impl Iterator<Item = (Option<&'i str>, &'v Value)>,
+    ) -> fmt::Result {
+        write!(f, r#"<ul class="list">"#)?;
+        for (key, value) in items {
+            write!(f, "<li>")?;
+            if let Some(key) = key {
+                write!(f, "<b>{}</b>: ", escape(key))?;
+            }
+            write!(f, "{}</li>", self.nested(value))?;
+        }
+        write!(f, "</ul>")
+    }
+
+    fn write_fields<'i>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        fields: impl Iterator<Item = (String, &'v Value)>,
+    ) -> fmt::Result {
+        write!(f, r#"<table class="fields">"#)?;
+        for (key, value) in fields.filter(|(_, value)| !value.is_null()) {
+            write!(
+                f,
+                r#"<tr><th class="align-right">{}</th><td>{}</td></tr>"#,
+                escape(&key),
+                self.nested(value)
+            )?;
+        }
+        write!(f, "</table>")
+    }
+
+    fn write_table(&self, f: &mut fmt::Formatter<'_>, rows: &'v [Value]) -> fmt::Result {
+        let mut columns: Vec<&str> = vec![];
+        for row in rows {
+            if let Value::Struct(s) = row {
+                for key in s.keys() {
+                    if !columns.contains(&key.as_str()) {
+                        columns.push(key);
+                    }
+                }
+            }
+        }
+
+        write!(f, r#"<table class="table">"#)?;
+        if !columns.is_empty() {
+            write!(f, "<thead><tr>")?;
+            for column in columns.iter() {
+                write!(f, "<th>{}</th>", escape(column))?;
+            }
+            write!(f, "</tr></thead>")?;
+        }
+        write!(f, "<tbody>")?;
+        for row in rows {
+            write!(f, "<tr>")?;
+            match row {
+                Value::Struct(s) => {
+                    for column in columns.iter() {
+                        match s.get(*column) {
+                            Some(value) => write!(f, "<td>{}</td>", self.nested(value))?,
+                            None => write!(f, "<td></td>")?,
+                        }
+                    }
+                }
+                value if columns.len() > 1 => write!(
+                    f,
+                    r#"<td colspan="{}">{}</td>"#,
+                    columns.len(),
+                    self.nested(value)
+                )?,
+                value => write!(f, "<td>{}</td>", self.nested(value))?,
+            }
+            write!(f, "</tr>")?;
+        }
+        write!(f, "</tbody></table>")
+    }
+
+    fn write_random_table(&self, f: &mut fmt::Formatter<'_>, table: &'v Table) -> fmt::Result {
+        write!(
+            f,
+            r#"<table class="table random-table"><thead><tr><th class="align-right">d{}</th><th></th></tr></thead><tbody>"#,
+            table.die()
+        )?;
+        for entry in &table.entries {
+            write!(
+                f,
+                r#"<tr><td class="align-right">{}</td><td>{}</td></tr>"#,
+                entry.range(),
+                self.nested(&entry.value)
+            )?;
+        }
+        write!(f, "</tbody></table>")
+    }
+}
+
+impl<'v> fmt::Display for HtmlValue<'v> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.value, self.layout) {
+            (Value::Number(n), _) => write!(f, "{}", n),
+            (Value::Boolean(b), _) => write!(f, "{}", b),
+            (Value::Null, _) => Ok(()),
+            (Value::String(s), _) => write!(f, "{}", escape(s)),
+            (Value::Date(date), _) => write!(f, r#"<time datetime="{0}">{0}</time>"#, date),
+            (Value::Array(_), Layout::Inline) | (Value::Struct(_), Layout::Inline) => {
+                self.write_inline(f)
+            }
+            (Value::Array(a), Layout::Table) => self.write_table(f, a),
+            (Value::Array(a), Layout::Fields) => self.write_fields(
+                f,
+                a.iter()
+                    .enumerate()
+                    .map(|(idx, value)| ((idx + 1).to_string(), value)),
+            ),
+            (Value::Array(a), Layout::Auto)
+                if !a.is_empty() && a.iter().all(|value| matches!(value, Value::Struct(_))) =>
+            {
+                self.write_table(f, a)
+            }
+            (Value::Array(a), _) => self.write_list(f, a.iter().map(|value| (None, value))),
+            (Value::Struct(s), Layout::List) => self.write_list(
+                f,
+                s.iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(key, value)| (Some(key.as_str()), value)),
+            ),
+            (Value::Struct(_), Layout::Table) => {
+                self.write_table(f, std::slice::from_ref(self.value))
+            }
+            (Value::Struct(s), _) => {
+                self.write_fields(f, s.iter().map(|(key, value)| (key.clone(), value)))
+            }
+            (Value::Tuple(values), _) => {
+                let values = values
+                    .iter()
+                    .map(|value| self.nested(value).to_string())
+                    .collect::<Vec<_>>();
+                let trailing = if values.len() == 1 { "," } else { "" };
+                write!(f, "({}{})", values.join(", "), trailing)
+            }
+            (Value::Enum(decl), _) => {
+                let variants = decl
+                    .variants
+                    .iter()
+                    .map(|variant| escape(&variant.name))
+                    .collect::<Vec<_>>();
+                write!(f, "{}", variants.join(" | "))
+            }
+            (Value::Variant(variant), _) => {
+                write!(f, "{}", escape(&variant.tag))?;
+                if !variant.fields.is_empty() {
+                    let fields = variant
+                        .field_names()
+                        .iter()
+                        .zip(variant.fields.iter())
+                        .map(|(name, value)| {
+                            format!("{}: {}", escape(name), render_value(value, self.policy))
+                        })
+                        .collect::<Vec<_>>();
+                    write!(f, " ({})", fields.join(", "))?;
+                }
+                Ok(())
+            }
+            (Value::Markdown(nodes), _) => markdown::write_html(f, nodes, self.policy),
+            (Value::Table(table), _) => self.write_random_table(f, table),
+            (Value::Lazy { .. }, _) => unreachable!("lazy render"),
         }
     }
 }
 
-pub(crate) fn render_value(value: &Value) -> HtmlValue {
-    HtmlValue { value }
+pub(crate) fn render_value(value: &Value, policy: HtmlPolicy) -> HtmlValue {
+    render_with_layout(value, policy, Layout::Auto)
+}
+
+pub(crate) fn render_with_layout(value: &Value, policy: HtmlPolicy, layout: Layout) -> HtmlValue {
+    HtmlValue {
+        value,
+        policy,
+        layout,
+    }
 }
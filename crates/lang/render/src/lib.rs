@@ -1,31 +1,166 @@
 use crate::db::Renderer;
+use crate::error::Error;
+use crate::html::Layout;
+use crate::markdown::{Links, Timelines};
+use crate::outline::Outline;
 use crate::result::RenderResult;
-use neu_eval::Value;
-use neu_parser::{NodeId, ParseResult};
+use crate::secret::Secret;
+use indexmap::IndexMap;
+use neu_analyze::Slugs;
+use neu_eval::{MdKind, MdNode, Value};
+use neu_parser::{Children, NodeId, ParseResult};
 use neu_syntax::ast::{ArticleItem, ArticleRef, Ast};
 use neu_syntax::db::FileId;
 use neu_syntax::Nodes;
 use std::collections::{BTreeMap, BTreeSet};
 
+mod error;
+mod graph;
 mod result;
 
+mod highlight;
 mod html;
+mod markdown;
+mod outline;
+mod sanitize;
+mod secret;
+mod timeline;
 
 pub mod db;
 
+pub use graph::{EdgeKind, Graph, GraphEdge, GraphFormat, GraphNode};
+pub use html::{escape, HtmlPolicy};
+pub use result::ArticleParts;
+pub use secret::Audience;
+pub use timeline::{timeline_sections, TimelineEntry, TimelineGroup, TimelineSection};
+
 fn eval(db: &dyn Renderer, file: FileId, id: NodeId, result: &mut RenderResult) -> Option<Value> {
     let evaled = db.eval(file, id);
     result.errors.merge(&evaled.errors);
     evaled.value.clone()
 }
 
-fn render_strukt(strukt: BTreeMap<String, Value>, result: &mut RenderResult) {
+pub fn value_html(value: &Value, policy: HtmlPolicy) -> String {
+    html::render_value(value, policy).to_string()
+}
+
+pub fn plain_text(value: &Value) -> String {
+    let mut text = String::new();
+    match value {
+        Value::String(s) => text.push_str(s),
+        Value::Markdown(nodes) => {
+            markdown::write_text(&mut text, nodes).expect("Writing to string");
+        }
+        value => text.push_str(&value.to_string()),
+    }
+    text
+}
+
+// Header fields shown to the current audience, without the `secret` marker itself.
+pub fn visible_fields(
+    mut fields: IndexMap<String, Value>,
+    audience: Audience,
+) -> IndexMap<String, Value> {
+    let secret = Secret::from_value(fields.shift_remove("secret").as_ref());
+    if audience == Audience::Player {
+        let secret = secret.unwrap_or(Secret::Public);
+        fields.retain(|field, _| !secret.hides(field));
+    }
+    fields
+}
+
+// The value written for `field` in a struct literal, so errors can point at the field itself.
+fn field_value(parsed: &ParseResult, input: &str, strukt: NodeId, field: &str) -> Option<NodeId> {
+    let node = parsed.arena.get(strukt);
+    if !node.is(Nodes::Struct) {
+        return None;
+    }
+    let mut children = node.children.iter().copied();
+    children.find(|child| {
+        let child = parsed.arena.get(*child);
+        child.is(Nodes::Key) && input[child.span] == *field
+    })?;
+    children.find(|child| parsed.arena.get(*child).is(Nodes::Value))
+}
+
+fn layouts(
+    strukt_id: Option<NodeId>,
+    layout: Option<Value>,
+    parsed: &ParseResult,
+    input: &str,
+    result: &mut RenderResult,
+) -> IndexMap<String, Layout> {
+    let mut layouts = IndexMap::new();
+    let (id, layout) = match (strukt_id, layout) {
+        (Some(id), Some(layout)) => (id, layout),
+        _ => return layouts,
+    };
+    let id = field_value(parsed, input, id, "layout").unwrap_or(id);
+    let layout = match layout.into_struct() {
+        Some(layout) => layout,
+        None => {
+            result.errors.add(id, Error::LayoutNotStruct.to_string());
+            return layouts;
+        }
+    };
+    for (field, hint) in layout {
+        let hint = match hint {
+            Value::String(hint) => hint,
+            hint => hint.to_string(),
+        };
+        match Layout::from_hint(&hint) {
+            Some(layout) => {
+                layouts.insert(field, layout);
+            }
+            None => {
+                let id = field_value(parsed, input, id, &field).unwrap_or(id);
+                result
+                    .errors
+                    .add(id, Error::UnknownLayout { field, hint }.to_string());
+            }
+        }
+    }
+    layouts
+}
+
+fn secret(strukt_id: Option<NodeId>, secret: Option<Value>, result: &mut RenderResult) -> Secret {
+    match (strukt_id, Secret::from_value(secret.as_ref())) {
+        (_, Some(secret)) => secret,
+        (Some(id), None) => {
+            result.errors.add(id, Error::SecretNotFields.to_string());
+            Secret::Public
+        }
+        (None, None) => Secret::Public,
+    }
+}
+
+fn render_strukt(
+    strukt: IndexMap<String, Value>,
+    layouts: &IndexMap<String, Layout>,
+    secret: &Secret,
+    policy: HtmlPolicy,
+    result: &mut RenderResult,
+) {
+    let strukt = strukt
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .collect::<Vec<_>>();
     if !strukt.is_empty() {
         result.output.push_str(r#"<table>"#);
         for (key, value) in strukt {
-            result.output.push_str("<tr>");
-            result.output.push_str(&format!(r#"<th class="align-right">{}</th>"#, key));
-            result.output.push_str(&format!("<td>{}</td>", html::render_value(&value)));
+            let layout = layouts.get(&key).copied().unwrap_or(Layout::Auto);
+            if secret.hides(&key) {
+                result.output.push_str(r#"<tr class="secret">"#);
+            } else {
+                result.output.push_str("<tr>");
+            }
+            result
+                .output
+                .push_str(&format!(r#"<th class="align-right">{}</th>"#, escape(&key)));
+            result.output.push_str(&format!(
+                "<td>{}</td>",
+                html::render_with_layout(&value, policy, layout)
+            ));
             result.output.push_str("</tr>");
         }
         result.output.push_str("</table>\n");
@@ -39,12 +174,17 @@ fn render_mentions(
     result: &mut RenderResult
 ) {
     if let (Some(kind), Some(id)) = (kind, id) {
-        let mentions = db
-            .all_mentions()
-            .into_iter()
-            .filter(|mention| mention.kind == kind)
-            .filter(|mention| mention.id == id)
-            .collect::<BTreeSet<_>>();
+        let mut mentions: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (_, _, mention) in db.visible_mention_nodes().iter().cloned() {
+            if mention.kind == kind && mention.id == id {
+                let backlinks = mentions
+                    .entry((mention.orig_kind, mention.orig_id, mention.section))
+                    .or_default();
+                if let Some(snippet) = mention.snippet {
+                    backlinks.insert((mention.anchor, snippet));
+                }
+            }
+        }
 
         if !mentions.is_empty() {
             result.output.push_str(r#"<table>"#);
@@ -52,33 +192,42 @@ fn render_mentions(
             result.output.push_str("<th>Mentioned in</th>");
             result.output.push_str("</tr>");
 
-            for mention in mentions {
-                let orig_item = db.find_md(mention.orig_kind.clone(), mention.orig_id.clone());
+            for ((orig_kind, orig_id, section), backlinks) in mentions {
+                let orig_item = db.find_md(orig_kind.clone(), orig_id.clone());
                 result.output.push_str("<tr><td>");
                 match orig_item {
                     Some((orig_path, orig_item)) => {
-                        let title = orig_item
-                            .strukt
-                            .and_then(|strukt| {
-                                let title = eval(db, orig_path, strukt, result)?
-                                    .into_struct()?
-                                    .remove("title")?;
-                                Some(html::render_value(&title).to_string())
-                            })
+                        let title = article_title(db, orig_path, &orig_item, result)
                             .unwrap_or_else(|| "???".into());
 
                         result.output.push_str(&format!(
                             r#"<a href="/{kind}/{id}">{title}</a>"#,
-                            kind = mention.orig_kind,
-                            id = mention.orig_id,
+                            kind = escape(&orig_kind),
+                            id = escape(&orig_id),
                             title = title
                         ));
+                        if let Some(section) = &section {
+                            result.output.push_str(&format!(
+                                r##" <a class="section" href="#{section}">#{section}</a>"##,
+                                section = escape(section)
+                            ));
+                        }
+                        for (anchor, snippet) in backlinks {
+                            let anchor = anchor.map(|anchor| format!("#{}", anchor));
+                            result.output.push_str(&format!(
+                                r#"<a class="backlink" href="/{kind}/{id}{anchor}">{snippet}</a>"#,
+                                kind = escape(&orig_kind),
+                                id = escape(&orig_id),
+                                anchor = escape(&anchor.unwrap_or_default()),
+                                snippet = escape(&snippet)
+                            ));
+                        }
                     }
                     None => {
                         result.output.push_str(&format!(
                             r#"<span class="error">Couldn't find {kind}:{id}</span>"#,
-                            kind = mention.orig_kind,
-                            id = mention.orig_id
+                            kind = escape(&orig_kind),
+                            id = escape(&orig_id)
                         ));
                     }
                 }
@@ -89,100 +238,420 @@ fn render_mentions(
     }
 }
 
+fn article_title(
+    db: &dyn Renderer,
+    path: FileId,
+    article_item: &ArticleItem,
+    result: &mut RenderResult,
+) -> Option<String> {
+    let title = eval(db, path, article_item.strukt?, result)?
+        .into_struct()?
+        .shift_remove("title")?;
+    Some(html::render_value(&title, db.html_policy()).to_string())
+}
+
+fn resolve_links(
+    db: &dyn Renderer,
+    nodes: &[MdNode],
+    links: &mut Links,
+    result: &mut RenderResult,
+) {
+    for node in nodes {
+        if let MdKind::WikiLink { kind, item_id, .. } = &node.kind {
+            let key = (kind.clone(), item_id.clone());
+            if !links.contains_key(&key) {
+                let fallback = escape(&format!("{}:{}", kind, item_id));
+                let found = db.find_md(kind.clone(), item_id.clone());
+                let title = found.map(|(path, article_item)| {
+                    article_title(db, path, &article_item, result).unwrap_or(fallback)
+                });
+                // Broken links stay `None`, the diagnostician reports them under the `links` lint.
+                links.insert(key, title);
+            }
+        }
+        resolve_links(db, &node.children, links, result);
+    }
+}
+
+fn resolve_timelines(db: &dyn Renderer, nodes: &[MdNode], timelines: &mut Timelines) {
+    for node in nodes {
+        let block = match &node.kind {
+            MdKind::CodeBlock(Some(info)) => markdown::code_text(&node.children)
+                .and_then(|body| Some((info, timeline::timeline_block(info, &body)?, body))),
+            _ => None,
+        };
+        if let Some((info, block, body)) = block {
+            let html = match block {
+                Ok((group, kinds)) => {
+                    let calendar = db.calendar();
+                    let entries = db.timeline();
+                    let sections = timeline_sections(&entries, &calendar, group, &kinds);
+                    timeline::write_timeline(&sections, &calendar)
+                }
+                Err(error) => format!(r#"<div class="error">{}</div>"#, escape(&error)),
+            };
+            timelines.insert((info.clone(), body), html);
+        }
+        resolve_timelines(db, &node.children, timelines);
+    }
+}
+
+// Secret links are left out of player pages.
+fn visible_md(db: &dyn Renderer, nodes: &[MdNode]) -> Vec<MdNode> {
+    match db.audience() {
+        Audience::Gm => nodes.to_vec(),
+        Audience::Player => secret::strip_links(nodes.to_vec(), &|kind, id| {
+            db.is_hidden(kind.into(), id.into())
+        }),
+    }
+}
+
 fn render_body(
     db: &dyn Renderer,
     file_id: FileId,
     article_item: &ArticleItem,
     parsed: &ParseResult,
+    outline: &mut Outline,
     result: &mut RenderResult
 ) {
-    let arena = &parsed.arena;
     if let Some(body) = article_item.body {
-        let body = arena
-            .get(body)
-            .children
-            .iter()
-            .copied()
-            .collect::<Vec<NodeId>>();
-        for body_id in body {
-            let body = arena.get(body_id);
-            if body.is(Nodes::Error) {
-                let err = parsed.errors.get(body_id).expect("Error");
-                let s = format!(r#"<div class="error">{}</div>"#, err);
-                result.output.push_str(&s);
-            } else if body.is(Nodes::Markdown) {
-                if let Some(markdown) = eval(db, file_id, body_id, result) {
-                    result.output.push_str(&format!("{}", html::render_value(&markdown)));
+        render_body_nodes(db, file_id, body, parsed, outline, result);
+    }
+}
+
+fn render_body_nodes(
+    db: &dyn Renderer,
+    file_id: FileId,
+    body: NodeId,
+    parsed: &ParseResult,
+    outline: &mut Outline,
+    result: &mut RenderResult,
+) {
+    let arena = &parsed.arena;
+    let body = arena
+        .get(body)
+        .children
+        .iter()
+        .copied()
+        .collect::<Vec<NodeId>>();
+    for body_id in body {
+        let body = arena.get(body_id);
+        if body.is(Nodes::Error) {
+            let err = parsed.errors.get(body_id).expect("Error");
+            let s = format!(r#"<div class="error">{}</div>"#, escape(err));
+            result.output.push_str(&s);
+        } else if body.is(Nodes::Markdown) {
+            if let Some(markdown) = eval(db, file_id, body_id, result) {
+                let policy = db.html_policy();
+                match &markdown {
+                    Value::Markdown(nodes) => {
+                        let nodes = &visible_md(db, nodes);
+                        let mut links = Links::default();
+                        resolve_links(db, nodes, &mut links, result);
+                        let mut timelines = Timelines::default();
+                        resolve_timelines(db, nodes, &mut timelines);
+                        markdown::report_html(nodes, policy, file_id, &mut result.errors);
+                        markdown::write_outlined(
+                            &mut result.output,
+                            nodes,
+                            policy,
+                            outline,
+                            &links,
+                            &timelines,
+                        )
+                        .expect("Writing to string");
+                    }
+                    markdown => result
+                        .output
+                        .push_str(&format!("{}", html::render_value(markdown, policy))),
                 }
-            } else if body.is(Nodes::ArticleItem) {
-                let input = db.input(file_id);
-                let article_item =
-                    ArticleItem::from_syntax(body_id, arena).expect("body is ArticleItem");
-                let kind = article_item.identifier(arena, &input).unwrap_or("???");
-                let id = article_item.item_id(arena, &input).unwrap_or("???");
-                result.output.push_str(&format!(
-                    r#"<div class="article-item" id="{}_{}" >"#,
-                    kind, id
-                ));
-                _render(db, file_id, article_item, parsed, result);
-                result.output.push_str("</div>\n");
-            } else if body.is(Nodes::ArticleRef) {
-                let input = db.input(file_id);
-                let article_ref =
-                    ArticleRef::from_syntax(body_id, arena).expect("body is ArticleRef");
-                let kind = article_ref.identifier(arena, &input).unwrap_or("???");
-                let id = article_ref.item_id(arena, &input).unwrap_or("???");
-                result.output.push_str(&format!(
-                    r#"<div class="article-item" id="{}_{}" >"#,
-                    kind, id
-                ));
-                let rendered = db.render_item(kind.into(), id.into());
-                result.output.push_str(&rendered.output);
+            }
+        } else if body.is(Nodes::ArticleItem) {
+            let input = db.input(file_id);
+            let article_item =
+                ArticleItem::from_syntax(body_id, arena).expect("body is ArticleItem");
+            let kind = article_item.identifier(arena, &input).unwrap_or("???");
+            let id = article_item.item_id(arena, &input).unwrap_or("???");
+            if db.is_hidden(kind.into(), id.into()) {
+                continue;
+            }
+            result.output.push_str(&format!(
+                r#"<div class="article-item" id="{}_{}" >"#,
+                escape(kind),
+                escape(id)
+            ));
+            _render(db, file_id, article_item, parsed, result);
+            result.output.push_str("</div>\n");
+        } else if body.is(Nodes::ArticleRef) {
+            let input = db.input(file_id);
+            let article_ref = ArticleRef::from_syntax(body_id, arena).expect("body is ArticleRef");
+            let kind = article_ref.identifier(arena, &input).unwrap_or("???");
+            let id = article_ref.item_id(arena, &input).unwrap_or("???");
+            if db.is_hidden(kind.into(), id.into()) {
+                continue;
+            }
+            match article_ref.section(arena, &input) {
+                None => {
+                    result.output.push_str(&format!(
+                        r#"<div class="article-item" id="{}_{}" >"#,
+                        escape(kind),
+                        escape(id)
+                    ));
+                    let rendered = db.render_item(kind.into(), id.into());
+                    result.output.push_str(&rendered.output);
+                }
+                Some(section) => {
+                    result.output.push_str(&format!(
+                        r#"<div class="article-section" id="{}_{}_{}" >"#,
+                        escape(kind),
+                        escape(id),
+                        escape(section)
+                    ));
+                    match render_section(db, kind, id, section, outline) {
+                        Some(rendered) => result.output.push_str(&rendered.output),
+                        // Missing articles are reported by the diagnostician.
+                        None if db.find_md(kind.into(), id.into()).is_none() => {
+                            let error = Error::ArticleNotFound {
+                                kind: kind.into(),
+                                id: id.into(),
+                            };
+                            result.output.push_str(&format!(
+                                r#"<div class="error">{}</div>"#,
+                                escape(&error.to_string())
+                            ));
+                        }
+                        None => {
+                            let error = Error::SectionNotFound {
+                                kind: kind.into(),
+                                id: id.into(),
+                                section: section.into(),
+                            }
+                            .to_string();
+                            result.output.push_str(&format!(
+                                r#"<div class="error">{}</div>"#,
+                                escape(&error)
+                            ));
+                            result.errors.add(body_id, error);
+                        }
+                    }
+                }
+            }
+            result.output.push_str("</div>\n");
+        } else if body.is(Nodes::Secret) {
+            if db.audience() == Audience::Player {
+                continue;
+            }
+            let mut children = Children::new(body.children.iter().copied(), arena);
+            if let Some((inner, _)) = children.find_node(Nodes::ArticleBody) {
+                result.output.push_str(r#"<div class="secret">"#);
+                render_body_nodes(db, file_id, inner, parsed, outline, result);
                 result.output.push_str("</div>\n");
-            } else {
-                let s = format!(r#"<div class="todo">{:?}</div>"#, body);
-                result.output.push_str(&s);
             }
+        } else {
+            let s = format!(
+                r#"<div class="todo">{}</div>"#,
+                escape(&format!("{:?}", body))
+            );
+            result.output.push_str(&s);
         }
     }
 }
 
+// Plain text of the markdown in the body, nested articles are left to themselves.
+pub(crate) fn body_text(
+    db: &dyn Renderer,
+    file_id: FileId,
+    body: NodeId,
+    parsed: &ParseResult,
+    text: &mut String,
+) {
+    let arena = &parsed.arena;
+    for body_id in arena.get(body).children.iter().copied() {
+        let body = arena.get(body_id);
+        if body.is(Nodes::Markdown) {
+            if let Some(Value::Markdown(nodes)) = db.eval(file_id, body_id).value.clone() {
+                let part = plain_text(&Value::Markdown(visible_md(db, &nodes)));
+                if !part.trim().is_empty() {
+                    text.push_str(part.trim());
+                    text.push('\n');
+                }
+            }
+        } else if body.is(Nodes::Secret) && db.audience() == Audience::Gm {
+            let mut children = Children::new(body.children.iter().copied(), arena);
+            if let Some((inner, _)) = children.find_node(Nodes::ArticleBody) {
+                body_text(db, file_id, inner, parsed, text);
+            }
+        }
+    }
+}
+
+fn capture(result: &mut RenderResult, f: impl FnOnce(&mut RenderResult)) -> String {
+    let outer = std::mem::take(&mut result.output);
+    f(result);
+    std::mem::replace(&mut result.output, outer)
+}
+
 fn _render(
     db: &dyn Renderer,
     file_id: FileId,
     article_item: ArticleItem,
     parsed: &ParseResult,
     result: &mut RenderResult,
-) {
+) -> ArticleParts {
     let input = db.input(file_id);
     let kind = article_item.identifier(&parsed.arena, &input);
     let id = article_item.item_id(&parsed.arena, &input);
+    let policy = db.html_policy();
 
     let mut strukt = article_item
         .strukt
         .and_then(|strukt| eval(db, file_id, strukt, result)?.into_struct())
         .unwrap_or_default();
 
-    if let Some(title) = strukt.remove("title") {
-        result
-            .output
-            .push_str(&format!("<h1>{}</h1>\n", html::render_value(&title)));
+    let title = strukt
+        .shift_remove("title")
+        .map(|title| html::render_value(&title, policy).to_string());
+    let layouts = layouts(
+        article_item.strukt,
+        strukt.shift_remove("layout"),
+        parsed,
+        &input,
+        result,
+    );
+    let secret = secret(article_item.strukt, strukt.shift_remove("secret"), result);
+    if db.audience() == Audience::Player {
+        strukt.retain(|field, _| !secret.hides(field));
     }
+    let side_table = capture(result, |result| {
+        render_strukt(strukt, &layouts, &secret, policy, result)
+    });
+    let mentions = capture(result, |result| render_mentions(db, kind, id, result));
+    let mut outline = Outline::default();
+    let body = capture(result, |result| {
+        render_body(db, file_id, &article_item, parsed, &mut outline, result)
+    });
+    let toc = outline.toc();
 
+    if let Some(title) = &title {
+        result.output.push_str(&format!("<h1>{}</h1>\n", title));
+    }
     result.output.push_str(r#"<div class="side-table">"#);
+    result.output.push_str(&side_table);
+    result.output.push_str(&toc);
+    result.output.push_str(&mentions);
+    result.output.push_str("</div>");
+    result.output.push_str(&body);
 
-    render_strukt(strukt, result);
-    render_mentions(db, kind, id, result);
+    ArticleParts {
+        title,
+        side_table,
+        toc,
+        mentions,
+        body,
+    }
+}
 
-    result.output.push_str("</div>");
+// Transcluded headings get their ids from the outline of the page they end up in.
+fn render_section(
+    db: &dyn Renderer,
+    kind: &str,
+    id: &str,
+    section: &str,
+    outline: &mut Outline,
+) -> Option<RenderResult> {
+    let (path, article_item) = db.find_md(kind.into(), id.into())?;
+    let parsed = db.parse_syntax(path);
+
+    let mut result = RenderResult::default();
+    if !_render_section(
+        db,
+        path,
+        &article_item,
+        &parsed,
+        section,
+        outline,
+        &mut result,
+    ) {
+        return None;
+    }
+    Some(result)
+}
+
+// Renders the part of the article body under the heading with the given slug,
+// up to the next heading of the same or higher level.
+fn _render_section(
+    db: &dyn Renderer,
+    file_id: FileId,
+    article_item: &ArticleItem,
+    parsed: &ParseResult,
+    section: &str,
+    outline: &mut Outline,
+    result: &mut RenderResult,
+) -> bool {
+    let arena = &parsed.arena;
+    let body = match article_item.body {
+        Some(body) => arena.get(body).children.clone(),
+        None => return false,
+    };
 
-    render_body(db, file_id, &article_item, parsed, result);
+    let mut slugs = Slugs::default();
+    let mut level = None;
+    let mut nodes = vec![];
+    'body: for body_id in body {
+        if !arena.get(body_id).is(Nodes::Markdown) {
+            continue;
+        }
+        let markdown = match eval(db, file_id, body_id, result) {
+            Some(Value::Markdown(markdown)) => markdown,
+            _ => continue,
+        };
+        for node in markdown {
+            if let MdKind::Heading(heading) = node.kind {
+                let mut text = String::new();
+                markdown::write_text(&mut text, &node.children).expect("Writing to string");
+                let slug = slugs.add(&text);
+                if let Some(level) = level {
+                    if heading <= level {
+                        break 'body;
+                    }
+                } else if slug == section {
+                    level = Some(heading);
+                }
+            }
+            if level.is_some() {
+                nodes.push(node);
+            }
+        }
+    }
+
+    if level.is_none() {
+        return false;
+    }
+    let policy = db.html_policy();
+    let nodes = visible_md(db, &nodes);
+    let mut links = Links::default();
+    resolve_links(db, &nodes, &mut links, result);
+    let mut timelines = Timelines::default();
+    resolve_timelines(db, &nodes, &mut timelines);
+    markdown::report_html(&nodes, policy, file_id, &mut result.errors);
+    markdown::write_outlined(
+        &mut result.output,
+        &nodes,
+        policy,
+        outline,
+        &links,
+        &timelines,
+    )
+    .expect("Writing to string");
+    true
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use neu_syntax::db::{FileKind, Parser};
+    use neu_eval::db::Evaluator;
+    use neu_parser::Diagnostics;
+    use neu_syntax::db::{load_files, FileKind, Parser};
     use std::sync::Arc;
 
     #[salsa::database(
@@ -198,17 +667,546 @@ mod tests {
 
     impl salsa::Database for TestDb {}
 
+    fn render(input: &str, policy: HtmlPolicy) -> Arc<RenderResult> {
+        render_for(input, policy, Audience::Gm)
+    }
+
+    fn test_db(files: &[(&str, &str)], policy: HtmlPolicy, audience: Audience) -> TestDb {
+        let mut db = TestDb::default();
+        load_files(&mut db, files);
+        db.set_html_policy(policy);
+        db.set_audience(audience);
+        db
+    }
+
+    fn render_for(input: &str, policy: HtmlPolicy, audience: Audience) -> Arc<RenderResult> {
+        let db = test_db(&[("test", input)], policy, audience);
+        db.render_md(db.file_id(("test".into(), FileKind::Md)))
+    }
+
     #[test]
     fn render_tests() {
         test_runner::test_snapshots("md", "render", |input| {
+            render(input, HtmlPolicy::Allow).display(input).to_string()
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn render_sanitized_tests() {
+        test_runner::test_snapshots("md", "render_sanitized", |input| {
+            render(input, HtmlPolicy::Sanitize)
+                .display(input)
+                .to_string()
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn render_stripped_tests() {
+        test_runner::test_snapshots("md", "render_stripped", |input| {
+            render(input, HtmlPolicy::Strip).display(input).to_string()
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn html_tests() {
+        test_runner::test_snapshots("neu", "html", |input| {
             let mut db = TestDb::default();
-            let path = db.file_id(("test".into(), FileKind::Md));
-            db.set_all_mds(Arc::new(Some(path.clone()).into_iter().collect()));
+            let path = db.file_id(("test".into(), FileKind::Neu));
+            db.set_all_mds(Default::default());
+            db.set_all_neu(Arc::new(Some(path.clone()).into_iter().collect()));
             db.set_input(path.clone(), Arc::new(input.into()));
-            let result = db.render_md(path);
+            let parsed = db.parse_syntax(path.clone());
+            let result = db.eval(path, parsed.root);
 
-            result.display(input).to_string()
+            match &result.value {
+                Some(value) => html::render_value(value, HtmlPolicy::default()).to_string(),
+                None => "None".into(),
+            }
         })
         .unwrap();
     }
+
+    fn errors(result: &RenderResult) -> Vec<String> {
+        result
+            .errors
+            .iter()
+            .map(|(_, error)| error.clone())
+            .collect()
+    }
+
+    const UNSAFE_ARTICLE: &str = r#"+++ test:1234abcd +++
+title = "<b>Title</b>"
++++
+
+<div class="note" onclick="steal()">Hello</div>
+"#;
+
+    #[test]
+    fn sanitize_reports_removed_html() {
+        let result = render(UNSAFE_ARTICLE, HtmlPolicy::Sanitize);
+        assert_eq!(
+            errors(&result),
+            vec![
+                r#"Disallowed HTML was removed from `<div class="note" onclick="steal()">Hello</div>`"#
+            ]
+        );
+        assert!(result.output.contains(r#"<div class="note">Hello</div>"#));
+        assert!(result.output.contains("<h1>&lt;b&gt;Title&lt;/b&gt;</h1>"));
+    }
+
+    #[test]
+    fn strip_reports_removed_html() {
+        let result = render(UNSAFE_ARTICLE, HtmlPolicy::Strip);
+        assert_eq!(
+            errors(&result),
+            vec![r#"Raw HTML was stripped: `<div class="note" onclick="steal()">Hello</div>`"#]
+        );
+        assert!(!result.output.contains("Hello"));
+    }
+
+    #[test]
+    fn allow_keeps_html() {
+        let result = render(UNSAFE_ARTICLE, HtmlPolicy::Allow);
+        assert!(errors(&result).is_empty());
+        assert!(result.output.contains(r#"onclick="steal()""#));
+    }
+
+    #[test]
+    fn html_from_other_files_is_not_reported() {
+        let db = test_db(
+            &[("test", UNSAFE_ARTICLE), ("notes.neu", "{}")],
+            HtmlPolicy::Sanitize,
+            Audience::Gm,
+        );
+        let file = db.file_id(("test".into(), FileKind::Md));
+        let notes = db.file_id(("notes.neu".into(), FileKind::Neu));
+        let id = db.parse_syntax(notes).root;
+        let html = |file| {
+            vec![MdNode::leaf(MdKind::Html {
+                file,
+                id,
+                html: r#"<b onclick="steal()">Run</b>"#.into(),
+            })]
+        };
+
+        let mut errors = Diagnostics::default();
+        markdown::report_html(&html(notes), HtmlPolicy::Sanitize, file, &mut errors);
+        assert_eq!(errors.iter().count(), 0);
+        markdown::report_html(&html(file), HtmlPolicy::Sanitize, file, &mut errors);
+        assert_eq!(errors.iter().count(), 1);
+    }
+
+    // The source text of the nodes errors are attached to.
+    fn error_spans(input: &str) -> Vec<&str> {
+        let db = test_db(&[("test", input)], HtmlPolicy::Allow, Audience::Gm);
+        let file = db.file_id(("test".into(), FileKind::Md));
+        let parsed = db.parse_syntax(file);
+        db.render_md(file)
+            .errors
+            .iter()
+            .map(|(id, _)| &input[parsed.arena.get(id).span])
+            .collect()
+    }
+
+    #[test]
+    fn unknown_layout_is_reported() {
+        let input = r#"+++ test:1234abcd +++
+title = "Borys"
+layout = { tags = "grid" }
+tags = ["smith"]
++++
+"#;
+        let result = render(input, HtmlPolicy::Allow);
+        assert_eq!(
+            errors(&result),
+            vec![
+                "Unknown layout `grid` for field `tags`, expected `auto`, `inline`, `list`, `table` or `fields`"
+            ]
+        );
+        assert_eq!(error_spans(input), vec![r#""grid""#]);
+        assert!(result
+            .output
+            .contains(r#"<td><ul class="list"><li>smith</li></ul></td>"#));
+    }
+
+    #[test]
+    fn layout_must_be_struct() {
+        let input = r#"+++ test:1234abcd +++
+layout = "list"
++++
+"#;
+        let result = render(input, HtmlPolicy::Allow);
+        assert_eq!(
+            errors(&result),
+            vec!["`layout` should be a struct of field layouts"]
+        );
+        assert_eq!(error_spans(input), vec![r#""list""#]);
+    }
+
+    #[test]
+    fn broken_wiki_link_is_marked() {
+        let result = render(
+            r#"+++ test:1234abcd +++
+title = "Borys"
++++
+
+[[test:1234abcd|Self]] and [[npc:ffffffff]]
+"#,
+            HtmlPolicy::Allow,
+        );
+        assert!(errors(&result).is_empty());
+        assert!(result
+            .output
+            .contains(r#"<a class="wiki-link broken" href="/npc/ffffffff">npc:ffffffff</a>"#));
+    }
+
+    const SECRET_ARTICLE: &str = r#"+++ sesja:a88907fd +++
+title = "Sesja"
+secret = ["loot"]
+loot = "Sztylet"
+place = "Kuźnia"
++++
+
+The party meets [[secret:npc:0123abcd|a stranger]] and [the smith](secret:npc:0123abcd).
+
+++ secret ++
+
+The stranger is a vampire.
+
+++ npc:0123abcd ++
+title = "Borys"
++++
+
+Kowal.
+
+++ end ++
+
+++ end ++
+"#;
+
+    #[test]
+    fn secrets_are_marked_for_gm() {
+        let result = render_for(SECRET_ARTICLE, HtmlPolicy::Allow, Audience::Gm);
+        assert!(errors(&result).is_empty());
+        assert!(result.output.contains(
+            r#"<tr class="secret"><th class="align-right">loot</th><td>Sztylet</td></tr>"#
+        ));
+        assert!(result.output.contains(concat!(
+            r#"<a class="wiki-link secret" href="/npc/0123abcd">a stranger</a> and "#,
+            r#"<a href="/npc/0123abcd">the smith</a>"#
+        )));
+        assert!(result
+            .output
+            .contains(r#"<div class="secret"><p>The stranger is a vampire.</p>"#));
+        assert!(result.output.contains(r#"id="npc_0123abcd""#));
+    }
+
+    #[test]
+    fn secrets_are_stripped_for_players() {
+        let result = render_for(SECRET_ARTICLE, HtmlPolicy::Allow, Audience::Player);
+        assert!(errors(&result).is_empty());
+        assert!(result.output.contains("Kuźnia"));
+        assert!(result
+            .output
+            .contains("<p>The party meets a stranger and the smith.</p>"));
+        for secret in &["Sztylet", "vampire", "Borys", "npc", "secret"] {
+            assert!(!result.output.contains(secret), "{} leaked", secret);
+        }
+    }
+
+    const SECRET_MENTIONS: &[(&str, &str)] = &[
+        (
+            "a.md",
+            r#"+++ sesja:a88907fd +++
+title = "Sesja"
++++
+
+++ secret ++
+
+## Skarb
+
+Ukryty w kuźni.
+
+++ end ++
+
+Spotkanie z [[npc:0123abcd|Borysem]] i [[secret:npc:0000000f|wampirem]].
+"#,
+        ),
+        (
+            "b.md",
+            r#"+++ npc:0123abcd +++
+title = "Borys"
++++
+"#,
+        ),
+    ];
+
+    #[test]
+    fn secret_parts_are_left_out_of_backlinks() {
+        let db = test_db(SECRET_MENTIONS, HtmlPolicy::Allow, Audience::Player);
+        let path = db.file_id(("b.md".into(), FileKind::Md));
+        let output = db.render_md(path).output.clone();
+        assert!(output
+            .contains(r#"<a class="backlink" href="/sesja/a88907fd">Spotkanie z Borysem i .</a>"#));
+        for secret in &["skarb", "wampir"] {
+            assert!(!output.to_lowercase().contains(secret), "{} leaked", secret);
+        }
+    }
+
+    #[test]
+    fn secret_must_list_fields() {
+        let result = render(
+            r#"+++ test:1234abcd +++
+secret = "notes"
++++
+"#,
+            HtmlPolicy::Allow,
+        );
+        assert_eq!(
+            errors(&result),
+            vec!["`secret` should be `true` or a list of field names"]
+        );
+    }
+
+    const GRAPH_ARTICLES: &[(&str, &str)] = &[
+        (
+            "a.md",
+            r#"+++ sesja:a88907fd +++
+title = "Sesja"
++++
+
+Spotkanie z [Borysem](npc:0123abcd) przy [mieczu](przedmiot:0000bbbb) i [[npc:ffffffff]].
+
+++ npc:0123abcd ++
+title = "Borys"
++++
+
+[+ miejsce:0000aaaa +]
+
+++ end ++
+"#,
+        ),
+        (
+            "b.md",
+            r#"+++ miejsce:0000aaaa +++
+title = "Kuźnia & Młot"
++++
+"#,
+        ),
+        (
+            "c.md",
+            r#"+++ przedmiot:0000bbbb +++
+title = "Miecz"
++++
+"#,
+        ),
+    ];
+
+    fn graph() -> Graph {
+        let db = test_db(GRAPH_ARTICLES, HtmlPolicy::Allow, Audience::Gm);
+        (*db.article_graph()).clone()
+    }
+
+    #[test]
+    fn article_graph() {
+        let graph = graph();
+        let edges = graph
+            .edges
+            .iter()
+            .map(|edge| format!("{} -> {} ({})", edge.from, edge.to, edge.via.name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                "npc:0123abcd -> miejsce:0000aaaa (ref)",
+                "sesja:a88907fd -> npc:0123abcd (nested)",
+                "sesja:a88907fd -> przedmiot:0000bbbb (link)",
+            ]
+        );
+
+        assert_eq!(
+            graph.edges[2].snippet.as_deref(),
+            Some("Spotkanie z Borysem przy mieczu i npc:ffffffff.")
+        );
+
+        let only = graph.filter(&[], Some(("miejsce", "0000aaaa")), Some(1));
+        assert_eq!(
+            only.export(GraphFormat::Dot),
+            concat!(
+                "digraph articles {\n",
+                r#"    "npc:0123abcd" [label="Borys", kind="npc", file="a.md"];"#,
+                "\n",
+                r#"    "miejsce:0000aaaa" [label="Kuźnia & Młot", kind="miejsce", file="b.md"];"#,
+                "\n",
+                r#"    "npc:0123abcd" -> "miejsce:0000aaaa" [via="ref"];"#,
+                "\n}\n"
+            )
+        );
+
+        let npcs = graph.filter(&["npc".to_string()], None, None);
+        assert_eq!(
+            npcs.export(GraphFormat::Json),
+            concat!(
+                r#"{"nodes":[{"id":"npc:0123abcd","kind":"npc","title":"Borys","file":"a.md"}],"#,
+                r#""edges":[]}"#,
+                "\n"
+            )
+        );
+        let graphml = graph.export(GraphFormat::GraphMl);
+        assert!(graphml.contains(r#"<data key="title">Kuźnia &amp; Młot</data>"#));
+        assert!(graphml.contains(
+            r#"<edge source="sesja:a88907fd" target="npc:0123abcd"><data key="via">nested</data></edge>"#
+        ));
+    }
+
+    const CALENDAR: &str = r#"{
+    months = [
+        { name = "Hammer", days = 30 },
+        { name = "Alturiak", days = 30 },
+        { name = "Ches", days = 30 }
+    ],
+    eras = [
+        { name = "Rachuba Dolin", start = 1 },
+        { name = "Dawne", start = -1000 }
+    ]
+}"#;
+
+    const TIMELINE_ARTICLES: &[(&str, &str)] = &[
+        ("calendar.neu", CALENDAR),
+        (
+            "a.md",
+            r#"+++ sesja:00000001 +++
+title = "Pierwsza sesja"
+date = @1372-02-03
++++
+
+++ wydarzenie:00000002 ++
+title = "Bitwa"
+date = @1372-02-05T14:30
++++
+++ end ++
+
+```timeline session
+wydarzenie
+```
+"#,
+        ),
+        (
+            "b.md",
+            r#"+++ sesja:00000003 +++
+title = "Druga sesja"
+date = @1372-03-01
++++
+"#,
+        ),
+        (
+            "c.md",
+            r#"+++ wydarzenie:0000000a +++
+title = "Założenie"
+date = @-5-01-01
++++
+"#,
+        ),
+    ];
+
+    #[test]
+    fn timeline_entries() {
+        let db = test_db(TIMELINE_ARTICLES, HtmlPolicy::Allow, Audience::Gm);
+
+        let calendar = db.calendar();
+        assert_eq!(calendar.eras[0].name, "Dawne");
+        assert!(calendar.check(&"1372-03-31".parse().unwrap()).is_err());
+
+        let entries = db.timeline();
+        let ids = entries
+            .iter()
+            .map(|entry| format!("{} {}", calendar.format(&entry.date), entry.id))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                "1 Hammer -5 0000000a",
+                "3 Alturiak 1372 00000001",
+                "5 Alturiak 1372, 14:30 00000002",
+                "1 Ches 1372 00000003",
+            ]
+        );
+
+        let eras = timeline_sections(&entries, &calendar, TimelineGroup::Era, &[])
+            .into_iter()
+            .map(|section| (section.title.unwrap(), section.entries.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            eras,
+            vec![("Dawne".to_string(), 1), ("Rachuba Dolin".to_string(), 3)]
+        );
+
+        let path = db.file_id(("a.md".into(), FileKind::Md));
+        assert!(db.render_md(path).output.contains(concat!(
+            r#"<div class="timeline"><h3><a href="/wydarzenie/0000000a">Założenie</a></h3>"#,
+            r#"<ol><li><time datetime="-5-01-01">1 Hammer -5</time> "#,
+            r#"<a href="/wydarzenie/0000000a">Założenie</a></li></ol>"#,
+            r#"<h3><a href="/sesja/00000001">Pierwsza sesja</a></h3>"#,
+            r#"<ol><li><time datetime="1372-02-05T14:30">5 Alturiak 1372, 14:30</time> "#,
+            r#"<a href="/wydarzenie/00000002">Bitwa</a></li></ol></div>"#
+        )));
+    }
+
+    #[test]
+    fn heading_slugs() {
+        use neu_analyze::slug;
+
+        assert_eq!(slug("Combat Rules"), "combat-rules");
+        assert_eq!(slug("  Who's who?  "), "whos-who");
+        assert_eq!(slug("snake_case -- dash"), "snake-case-dash");
+        assert_eq!(slug("Pośród Drzew"), "pośród-drzew");
+        assert_eq!(slug("!!!"), "section");
+
+        let mut outline = Outline::default();
+        assert_eq!(outline.add(2, "Combat"), "combat");
+        assert_eq!(outline.add(2, "Combat"), "combat-1");
+        assert_eq!(outline.add(3, "Combat"), "combat-2");
+    }
+
+    #[test]
+    fn table_of_contents() {
+        let mut outline = Outline::default();
+        assert_eq!(outline.toc(), "");
+
+        outline.add(2, "A");
+        outline.add(3, "B");
+        outline.add(4, "C");
+        outline.add(2, "D");
+        assert_eq!(
+            outline.toc(),
+            concat!(
+                r##"<nav class="toc"><ul><li><a href="#a">A</a>"##,
+                r##"<ul><li><a href="#b">B</a><ul><li><a href="#c">C</a></li></ul></li></ul></li>"##,
+                r##"<li><a href="#d">D</a></li></ul></nav>"##
+            )
+        );
+    }
+
+    #[test]
+    fn sanitize_html() {
+        use crate::sanitize::sanitize;
+
+        assert_eq!(sanitize("<b>bold</b>"), ("<b>bold</b>".into(), false));
+        assert_eq!(
+            sanitize(r#"<a href="javascript:alert(1)" title='x'>link</a>"#),
+            (r#"<a title="x">link</a>"#.into(), true)
+        );
+        assert_eq!(
+            sanitize("<p>a<script>alert(1)</script>b</p>"),
+            ("<p>ab</p>".into(), true)
+        );
+        assert_eq!(sanitize("<!-- note -->1 < 2"), ("1 &lt; 2".into(), true));
+        assert_eq!(
+            sanitize(r#"<img src="/a.png" onerror="x()"/>"#),
+            (r#"<img src="/a.png"/>"#.into(), true)
+        );
+    }
 }
@@ -0,0 +1,379 @@
+use crate::error::Error;
+use crate::highlight::{highlight, highlight_neu};
+use crate::html::{escape, HtmlPolicy};
+use crate::outline::Outline;
+use crate::sanitize::{is_safe_url, sanitize};
+use neu_eval::{MdAlign, MdKind, MdNode, Value};
+use neu_parser::{Diagnostics, NodeId};
+use neu_syntax::db::FileId;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+// Titles of the articles behind `[[kind:id]]` links, `None` when the article doesn't exist.
+pub(crate) type Links = HashMap<(String, String), Option<String>>;
+
+// Rendered ```timeline``` blocks keyed by their info string and body.
+pub(crate) type Timelines = HashMap<(String, String), String>;
+
+pub(crate) fn write_html(w: &mut dyn Write, nodes: &[MdNode], policy: HtmlPolicy) -> fmt::Result {
+    write_outlined(
+        w,
+        nodes,
+        policy,
+        &mut Outline::default(),
+        &Links::default(),
+        &Timelines::default(),
+    )
+}
+
+pub(crate) fn write_outlined(
+    w: &mut dyn Write,
+    nodes: &[MdNode],
+    policy: HtmlPolicy,
+    outline: &mut Outline,
+    links: &Links,
+    timelines: &Timelines,
+) -> fmt::Result {
+    let mut writer = HtmlWriter {
+        w,
+        policy,
+        outline,
+        links,
+        timelines,
+        code: false,
+    };
+    writer.write_all(nodes)
+}
+
+struct HtmlWriter<'w> {
+    w: &'w mut dyn Write,
+    policy: HtmlPolicy,
+    outline: &'w mut Outline,
+    links: &'w Links,
+    timelines: &'w Timelines,
+    code: bool,
+}
+
+impl<'w> HtmlWriter<'w> {
+    fn write_all(&mut self, nodes: &[MdNode]) -> fmt::Result {
+        for node in nodes {
+            self.write_node(node)?;
+        }
+        Ok(())
+    }
+
+    fn wrap(&mut self, open: &str, node: &MdNode, close: &str) -> fmt::Result {
+        self.w.write_str(open)?;
+        self.write_all(&node.children)?;
+        self.w.write_str(close)
+    }
+
+    // Link destinations don't go through `sanitize`, so unsafe schemes are dropped here.
+    fn safe_url(&self, url: &str) -> String {
+        if self.policy != HtmlPolicy::Allow && !is_safe_url(url) {
+            "#".into()
+        } else {
+            url.into()
+        }
+    }
+
+    fn write_node(&mut self, node: &MdNode) -> fmt::Result {
+        let tag = match &node.kind {
+            MdKind::Paragraph => "p",
+            MdKind::Heading(level) => {
+                let mut text = String::new();
+                write_text(&mut text, &node.children)?;
+                let slug = self.outline.add(*level, &text);
+                let open = format!(r#"<h{} id="{}">"#, level, escape(&slug));
+                return self.wrap(&open, node, &format!("</h{}>", level));
+            }
+            MdKind::Emphasis => "em",
+            MdKind::Strong => "strong",
+            MdKind::Strikethrough => "del",
+            MdKind::BlockQuote => "blockquote",
+            MdKind::UnorderedList => "ul",
+            MdKind::OrderedList => "ol",
+            MdKind::ListItem => "li",
+            MdKind::Table => "table",
+            MdKind::TableRow => "tr",
+            MdKind::TableHead => return self.wrap("<thead><tr>", node, "</tr></thead>"),
+            MdKind::TableCell { head, align } => {
+                let tag = if *head { "th" } else { "td" };
+                let open = match align {
+                    Some(align) => {
+                        format!(r#"<{} style="text-align: {}">"#, tag, align_str(*align))
+                    }
+                    None => format!("<{}>", tag),
+                };
+                return self.wrap(&open, node, &format!("</{}>", tag));
+            }
+            MdKind::CodeBlock(lang) => {
+                let timeline = lang
+                    .clone()
+                    .and_then(|info| Some((info, code_text(&node.children)?)))
+                    .and_then(|key| self.timelines.get(&key));
+                if let Some(timeline) = timeline {
+                    return self.w.write_str(timeline);
+                }
+                let lang = lang
+                    .as_deref()
+                    .and_then(|lang| lang.split(|c: char| c.is_whitespace() || c == ',').next())
+                    .filter(|lang| !lang.is_empty());
+                let open = match lang {
+                    Some(lang) => format!(r#"<pre><code class="language-{}">"#, escape(lang)),
+                    None => "<pre><code>".into(),
+                };
+                let highlighted =
+                    lang.and_then(|lang| highlight(lang, &code_text(&node.children)?));
+                if let Some(highlighted) = highlighted {
+                    return write!(self.w, "{}{}</code></pre>", open, highlighted);
+                }
+                self.code = true;
+                let result = self.wrap(&open, node, "</code></pre>");
+                self.code = false;
+                return result;
+            }
+            MdKind::Image(src) => {
+                let open = format!(r#"<img src="{}">"#, escape(&self.safe_url(src)));
+                return self.wrap(&open, node, "</img>");
+            }
+            MdKind::Link(url) => {
+                let open = format!(r#"<a href="{}">"#, escape(&self.safe_url(&link_href(url))));
+                return self.wrap(&open, node, "</a>");
+            }
+            MdKind::WikiLink {
+                kind,
+                item_id,
+                label,
+                secret,
+                ..
+            } => {
+                let target = self.links.get(&(kind.clone(), item_id.clone()));
+                let class = match (target, secret) {
+                    (Some(None), _) => "wiki-link broken",
+                    (_, true) => "wiki-link secret",
+                    _ => "wiki-link",
+                };
+                let text = match (label, target) {
+                    (Some(label), _) => escape_text(label),
+                    (None, Some(Some(title))) => title.clone(),
+                    (None, _) => escape(&format!("{}:{}", kind, item_id)),
+                };
+                return write!(
+                    self.w,
+                    r#"<a class="{}" href="/{}/{}">{}</a>"#,
+                    class,
+                    escape(kind),
+                    escape(item_id),
+                    text
+                );
+            }
+            MdKind::FootnoteReference(label) => {
+                return write!(
+                    self.w,
+                    r##"<sup class="footnote-reference"><a href="#fn-{label}">{label}</a></sup>"##,
+                    label = escape(label)
+                );
+            }
+            MdKind::FootnoteDefinition(label) => {
+                let open = format!(
+                    r#"<div class="footnote-definition" id="fn-{label}"><sup class="footnote-definition-label">{label}</sup>"#,
+                    label = escape(label)
+                );
+                return self.wrap(&open, node, "</div>");
+            }
+            MdKind::TaskListMarker(true) => {
+                return write!(self.w, r#"<input disabled="" type="checkbox" checked=""/>"#)
+            }
+            MdKind::TaskListMarker(false) => {
+                return write!(self.w, r#"<input disabled="" type="checkbox"/>"#)
+            }
+            MdKind::Rule => return write!(self.w, "<hr/>"),
+            MdKind::SoftBreak => return writeln!(self.w),
+            MdKind::HardBreak => return write!(self.w, "<br/>"),
+            MdKind::Text(text) if self.code => return write!(self.w, "{}", escape(text)),
+            MdKind::Text(text) => return write!(self.w, "{}", escape_text(text)),
+            MdKind::Html { html, .. } => {
+                return match self.policy {
+                    HtmlPolicy::Allow => write!(self.w, "{}", html),
+                    HtmlPolicy::Sanitize => write!(self.w, "{}", sanitize(html).0),
+                    HtmlPolicy::Strip => Ok(()),
+                }
+            }
+            MdKind::Code(value) => {
+                let value = format!("{:#}", value);
+                return write!(self.w, "<pre><code>{}</code></pre>", escape(&value));
+            }
+            MdKind::CodeShow { source, value } => {
+                let value = format!("{:#}", value);
+                return write!(
+                    self.w,
+                    r#"<div class="code-show"><pre><code class="language-neu">{}</code></pre><pre><code class="code-result">{}</code></pre></div>"#,
+                    highlight_neu(source),
+                    escape(&value)
+                );
+            }
+            MdKind::Value(value) => {
+                return match value.as_ref() {
+                    Value::String(s) => write!(self.w, "{}", escape(s)),
+                    value => write!(self.w, "{}", escape(&value.to_string())),
+                }
+            }
+        };
+
+        self.wrap(&format!("<{}>", tag), node, &format!("</{}>", tag))
+    }
+}
+
+pub(crate) fn code_text(nodes: &[MdNode]) -> Option<String> {
+    let mut code = String::new();
+    for node in nodes {
+        match &node.kind {
+            MdKind::Text(text) => code.push_str(text),
+            _ => return None,
+        }
+    }
+    Some(code)
+}
+
+// Markdown text is a slice of the source, so entity references written by the author are kept.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (idx, c) in text.char_indices() {
+        match c {
+            '&' if is_entity(&text[idx..]) => escaped.push('&'),
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn is_entity(text: &str) -> bool {
+    let end = match text.find(';') {
+        Some(end) if end > 1 && end <= 32 => end,
+        _ => return false,
+    };
+    let name = &text[1..end];
+    if !name.starts_with('#') {
+        return name.chars().all(|c| c.is_ascii_alphanumeric());
+    }
+    let code = &name[1..];
+    match code.chars().next() {
+        Some('x') | Some('X') => code.len() > 1 && code[1..].chars().all(|c| c.is_ascii_hexdigit()),
+        _ => !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()),
+    }
+}
+
+// Html that came from another file, through an import or a transclusion, isn't reported here.
+pub(crate) fn report_html(
+    nodes: &[MdNode],
+    policy: HtmlPolicy,
+    file: FileId,
+    errors: &mut Diagnostics<NodeId>,
+) {
+    for node in nodes {
+        match &node.kind {
+            MdKind::Html {
+                file: origin,
+                id,
+                html,
+            } if *origin == file => {
+                let snippet = html.trim().to_string();
+                match policy {
+                    HtmlPolicy::Allow => (),
+                    HtmlPolicy::Sanitize => {
+                        if sanitize(html).1 {
+                            errors.add(*id, Error::HtmlSanitized(snippet).to_string());
+                        }
+                    }
+                    HtmlPolicy::Strip => {
+                        if !snippet.is_empty() {
+                            errors.add(*id, Error::HtmlStripped(snippet).to_string());
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+        report_html(&node.children, policy, file, errors);
+    }
+}
+
+fn align_str(align: MdAlign) -> &'static str {
+    match align {
+        MdAlign::Left => "left",
+        MdAlign::Center => "center",
+        MdAlign::Right => "right",
+    }
+}
+
+static LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([a-z_A-Z0-9]+):([0-9A-Fa-f]{8})(#[\w-]+)?$").expect("Regex"));
+
+// `kind:id#section` links to other articles.
+pub(crate) fn link_target(url: &str) -> Option<(&str, &str, &str)> {
+    let url = url.trim_start_matches("secret:");
+    let cap = LINK_REGEX.captures(url)?;
+    let kind = cap.get(1).expect("G1").as_str();
+    let id = cap.get(2).expect("G2").as_str();
+    let section = cap
+        .get(3)
+        .map(|section| section.as_str())
+        .unwrap_or_default();
+    Some((kind, id, section))
+}
+
+fn link_href(url: &str) -> String {
+    match link_target(url) {
+        Some((kind, id, section)) => format!("/{}/{}{}", kind, id, section),
+        None => url.into(),
+    }
+}
+
+pub(crate) fn write_text(w: &mut dyn Write, nodes: &[MdNode]) -> fmt::Result {
+    for (idx, node) in nodes.iter().enumerate() {
+        if idx > 0 && is_block(&node.kind) {
+            writeln!(w)?;
+        }
+        match &node.kind {
+            MdKind::Text(text) => write!(w, "{}", text)?,
+            MdKind::SoftBreak | MdKind::HardBreak => write!(w, " ")?,
+            MdKind::Html { .. } | MdKind::Rule | MdKind::TaskListMarker(_) => (),
+            MdKind::FootnoteReference(label) => write!(w, "[{}]", label)?,
+            MdKind::WikiLink {
+                label: Some(label), ..
+            } => write!(w, "{}", label)?,
+            MdKind::WikiLink { kind, item_id, .. } => write!(w, "{}:{}", kind, item_id)?,
+            MdKind::Code(value) | MdKind::CodeShow { value, .. } => write!(w, "{:#}", value)?,
+            MdKind::Value(value) => match value.as_ref() {
+                Value::String(s) => write!(w, "{}", s)?,
+                value => write!(w, "{}", value)?,
+            },
+            _ => write_text(w, &node.children)?,
+        }
+    }
+    Ok(())
+}
+
+fn is_block(kind: &MdKind) -> bool {
+    matches!(
+        kind,
+        MdKind::Paragraph
+            | MdKind::Heading(_)
+            | MdKind::BlockQuote
+            | MdKind::UnorderedList
+            | MdKind::OrderedList
+            | MdKind::ListItem
+            | MdKind::CodeBlock(_)
+            | MdKind::Table
+            | MdKind::TableHead
+            | MdKind::TableRow
+            | MdKind::FootnoteDefinition(_)
+    )
+}
@@ -0,0 +1,65 @@
+use crate::html::escape;
+use neu_analyze::Slugs;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Outline {
+    slugs: Slugs,
+    pub headings: Vec<Heading>,
+}
+
+impl Outline {
+    pub fn add(&mut self, level: u8, text: &str) -> String {
+        let slug = self.slugs.add(text);
+        self.headings.push(Heading {
+            level,
+            text: text.to_string(),
+            slug: slug.clone(),
+        });
+        slug
+    }
+
+    pub fn toc(&self) -> String {
+        if self.headings.is_empty() {
+            return String::new();
+        }
+        let mut toc = String::from(r#"<nav class="toc">"#);
+        let mut levels: Vec<u8> = vec![];
+        for heading in self.headings.iter() {
+            while levels.len() > 1 && levels.last() > Some(&heading.level) {
+                toc.push_str("</li></ul>");
+                levels.pop();
+            }
+            match levels.last_mut() {
+                None => {
+                    toc.push_str("<ul>");
+                    levels.push(heading.level);
+                }
+                Some(last) if *last < heading.level => {
+                    toc.push_str("<ul>");
+                    levels.push(heading.level);
+                }
+                Some(last) => {
+                    *last = heading.level;
+                    toc.push_str("</li>");
+                }
+            }
+            toc.push_str(&format!(
+                r##"<li><a href="#{}">{}</a>"##,
+                escape(&heading.slug),
+                escape(&heading.text)
+            ));
+        }
+        for _ in levels {
+            toc.push_str("</li></ul>");
+        }
+        toc.push_str("</nav>");
+        toc
+    }
+}
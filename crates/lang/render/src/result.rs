@@ -4,7 +4,17 @@ use std::fmt;
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct RenderResult {
     pub output: String,
-    pub errors: Diagnostics<NodeId>
+    pub errors: Diagnostics<NodeId>,
+    pub parts: ArticleParts,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ArticleParts {
+    pub title: Option<String>,
+    pub side_table: String,
+    pub toc: String,
+    pub mentions: String,
+    pub body: String,
 }
 
 impl RenderResult {
@@ -0,0 +1,304 @@
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "alt", "class", "colspan", "href", "id", "rowspan", "src", "title",
+];
+
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+const DROP_CONTENT: &[&str] = &["script", "style"];
+
+struct Tag<'s> {
+    closing: bool,
+    name: String,
+    attributes: Vec<(&'s str, Option<&'s str>)>,
+    self_closing: bool,
+}
+
+// Returns sanitized html and whether anything was removed.
+pub(crate) fn sanitize(html: &str) -> (String, bool) {
+    let mut output = String::new();
+    let mut removed = false;
+    let mut dropping: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        if dropping.is_none() {
+            output.push_str(&rest[..start]);
+        }
+        rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            let end = rest
+                .find("-->")
+                .map(|end| end + 3)
+                .unwrap_or_else(|| rest.len());
+            rest = &rest[end..];
+            removed = true;
+            continue;
+        }
+
+        let tag = match tag_end(rest).and_then(|end| Some((parse_tag(&rest[1..end])?, end))) {
+            Some((tag, end)) => {
+                rest = &rest[end + 1..];
+                tag
+            }
+            None => {
+                if dropping.is_none() {
+                    output.push_str("&lt;");
+                }
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        if let Some(name) = &dropping {
+            if tag.closing && &tag.name == name {
+                dropping = None;
+            }
+            continue;
+        }
+
+        if DROP_CONTENT.contains(&tag.name.as_str()) {
+            if !tag.closing && !tag.self_closing {
+                dropping = Some(tag.name);
+            }
+            removed = true;
+            continue;
+        }
+
+        if !ALLOWED_TAGS.contains(&tag.name.as_str()) {
+            removed = true;
+            continue;
+        }
+
+        if tag.closing {
+            output.push_str(&format!("</{}>", tag.name));
+            continue;
+        }
+
+        output.push_str(&format!("<{}", tag.name));
+        for (name, value) in tag.attributes {
+            let name = name.to_lowercase();
+            if !ALLOWED_ATTRIBUTES.contains(&name.as_str()) {
+                removed = true;
+                continue;
+            }
+            match value {
+                Some(value) if URL_ATTRIBUTES.contains(&name.as_str()) && !is_safe_url(value) => {
+                    removed = true;
+                }
+                Some(value) => {
+                    output.push_str(&format!(r#" {}="{}""#, name, value.replace('"', "&quot;")))
+                }
+                None => output.push_str(&format!(" {}", name)),
+            }
+        }
+        output.push_str(if tag.self_closing { "/>" } else { ">" });
+    }
+
+    if dropping.is_none() {
+        output.push_str(rest);
+    }
+
+    (output, removed)
+}
+
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, c) in s.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '>') => return Some(idx),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            _ => (),
+        }
+    }
+    None
+}
+
+fn parse_tag(inner: &str) -> Option<Tag> {
+    let closing = inner.starts_with('/');
+    let inner = if closing { &inner[1..] } else { inner };
+    if !inner.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name_len = inner
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or_else(|| inner.len());
+    let name = inner[..name_len].to_lowercase();
+    let mut rest = inner[name_len..].trim_end();
+    let self_closing = rest.ends_with('/');
+    if self_closing {
+        rest = &rest[..rest.len() - 1];
+    }
+
+    let mut attributes = vec![];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            break;
+        }
+        let name_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or_else(|| rest.len());
+        let attribute = &rest[..name_len];
+        rest = rest[name_len..].trim_start();
+
+        let value = if rest.starts_with('=') {
+            let after = rest[1..].trim_start();
+            let (value, remaining) = match after.chars().next() {
+                Some(q) if q == '"' || q == '\'' => {
+                    let end = after[1..].find(q).map(|end| end + 1)?;
+                    (&after[1..end], &after[end + 1..])
+                }
+                _ => {
+                    let end = after
+                        .find(char::is_whitespace)
+                        .unwrap_or_else(|| after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            rest = remaining;
+            Some(value)
+        } else {
+            None
+        };
+        if !attribute.is_empty() {
+            attributes.push((attribute, value));
+        }
+    }
+
+    Some(Tag {
+        closing,
+        name,
+        attributes,
+        self_closing,
+    })
+}
+
+// Anything with a scheme outside of `URL_SCHEMES` is unsafe, relative urls are fine.
+pub(crate) fn is_safe_url(url: &str) -> bool {
+    let url = decode_entities(url)
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_lowercase();
+    match url.find(|c: char| c == ':' || c == '/' || c == '?' || c == '#') {
+        Some(idx) if url[idx..].starts_with(':') => URL_SCHEMES.contains(&&url[..idx]),
+        _ => true,
+    }
+}
+
+// Browsers decode character references before reading the scheme, e.g. `jav&#x61;script:`.
+fn decode_entities(s: &str) -> String {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(idx) = rest.find('&') {
+        decoded.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        match decode_entity(&rest[1..]) {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[1 + len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// Character and length of the reference after `&`, the trailing `;` is optional.
+fn decode_entity(s: &str) -> Option<(char, usize)> {
+    let (digits, radix, prefix) = if s.starts_with("#x") || s.starts_with("#X") {
+        (&s[2..], 16, 2)
+    } else if s.starts_with('#') {
+        (&s[1..], 10, 1)
+    } else {
+        let len = s
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or_else(|| s.len());
+        let c = match &s[..len] {
+            "colon" => ':',
+            "Tab" => '\t',
+            "NewLine" => '\n',
+            "amp" => '&',
+            "sol" => '/',
+            "num" => '#',
+            "quest" => '?',
+            _ => return None,
+        };
+        return Some((c, len + semicolon(&s[len..])));
+    };
+    let len = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or_else(|| digits.len());
+    if len == 0 {
+        return None;
+    }
+    let c = u32::from_str_radix(&digits[..len], radix)
+        .ok()
+        .and_then(std::char::from_u32)
+        .unwrap_or('\u{fffd}');
+    Some((c, prefix + len + semicolon(&digits[len..])))
+}
+
+fn semicolon(s: &str) -> usize {
+    if s.starts_with(';') {
+        1
+    } else {
+        0
+    }
+}
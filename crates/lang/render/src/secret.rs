@@ -0,0 +1,94 @@
+use crate::markdown::link_target;
+use neu_eval::{MdKind, MdNode, Value};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Audience {
+    Gm,
+    Player,
+}
+
+impl Default for Audience {
+    fn default() -> Self {
+        Self::Gm
+    }
+}
+
+impl FromStr for Audience {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gm" => Ok(Self::Gm),
+            "player" => Ok(Self::Player),
+            _ => Err(format!(
+                "Unknown audience `{}`, expected `gm` or `player`",
+                s
+            )),
+        }
+    }
+}
+
+// `secret = true` hides the whole article, `secret = ["notes"]` only the listed fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Secret {
+    Public,
+    Article,
+    Fields(Vec<String>),
+}
+
+impl Secret {
+    pub(crate) fn from_value(value: Option<&Value>) -> Option<Self> {
+        match value {
+            None | Some(Value::Boolean(false)) => Some(Self::Public),
+            Some(Value::Boolean(true)) => Some(Self::Article),
+            Some(Value::Array(fields)) => fields
+                .iter()
+                .map(|field| match field {
+                    Value::String(field) => Some(field.clone()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Self::Fields),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn hides(&self, field: &str) -> bool {
+        match self {
+            Self::Fields(fields) => fields.iter().any(|secret| secret == field),
+            _ => false,
+        }
+    }
+}
+
+// Players keep the text of secret links and links to hidden articles, but not the link.
+pub(crate) fn strip_links(nodes: Vec<MdNode>, hidden: &dyn Fn(&str, &str) -> bool) -> Vec<MdNode> {
+    nodes
+        .into_iter()
+        .flat_map(|node| {
+            let children = strip_links(node.children, hidden);
+            match node.kind {
+                MdKind::WikiLink {
+                    kind,
+                    item_id,
+                    label,
+                    secret,
+                    ..
+                } if secret || hidden(&kind, &item_id) => label
+                    .map(|label| MdNode {
+                        kind: MdKind::Text(label),
+                        children: vec![],
+                    })
+                    .into_iter()
+                    .collect(),
+                MdKind::Link(url) if is_hidden_link(&url, hidden) => children,
+                kind => vec![MdNode { kind, children }],
+            }
+        })
+        .collect()
+}
+
+fn is_hidden_link(url: &str, hidden: &dyn Fn(&str, &str) -> bool) -> bool {
+    url.starts_with("secret:") || link_target(url).map_or(false, |(kind, id, _)| hidden(kind, id))
+}
@@ -0,0 +1,138 @@
+use crate::html::escape;
+use neu_eval::{Calendar, Date};
+use neu_parser::NodeId;
+use neu_syntax::db::FileId;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub date: Date,
+    // The top level article (kind, id, title) when the entry is a nested item.
+    pub session: Option<(String, String, String)>,
+    pub file: FileId,
+    pub node: NodeId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineGroup {
+    Era,
+    Session,
+}
+
+impl Default for TimelineGroup {
+    fn default() -> Self {
+        Self::Era
+    }
+}
+
+impl FromStr for TimelineGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "era" => Ok(Self::Era),
+            "session" => Ok(Self::Session),
+            _ => Err(format!(
+                "Unknown timeline grouping `{}`, expected `era` or `session`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineSection<'a> {
+    pub title: Option<String>,
+    pub article: Option<(String, String)>,
+    pub entries: Vec<&'a TimelineEntry>,
+}
+
+pub fn timeline_sections<'a>(
+    entries: &'a [TimelineEntry],
+    calendar: &Calendar,
+    group: TimelineGroup,
+    kinds: &[String],
+) -> Vec<TimelineSection<'a>> {
+    let mut sections: Vec<TimelineSection<'a>> = vec![];
+    for entry in entries
+        .iter()
+        .filter(|entry| kinds.is_empty() || kinds.contains(&entry.kind))
+    {
+        let (title, article) = match (group, &entry.session) {
+            (TimelineGroup::Era, _) => {
+                let era = calendar.era(entry.date.year);
+                (era.map(|era| era.name.clone()), None)
+            }
+            (TimelineGroup::Session, Some((kind, id, title))) => {
+                (Some(title.clone()), Some((kind.clone(), id.clone())))
+            }
+            (TimelineGroup::Session, None) => (
+                Some(entry.title.clone()),
+                Some((entry.kind.clone(), entry.id.clone())),
+            ),
+        };
+
+        let section = sections
+            .iter_mut()
+            .find(|section| section.title == title && section.article == article);
+        match section {
+            Some(section) => section.entries.push(entry),
+            None => sections.push(TimelineSection {
+                title,
+                article,
+                entries: vec![entry],
+            }),
+        }
+    }
+    sections
+}
+
+// ```timeline session``` blocks take the grouping from the info string and the kinds from the body.
+pub(crate) fn timeline_block(
+    info: &str,
+    body: &str,
+) -> Option<Result<(TimelineGroup, Vec<String>), String>> {
+    let mut words = info.split_whitespace();
+    if words.next() != Some("timeline") {
+        return None;
+    }
+    let group = match words.next() {
+        Some(group) => group.parse(),
+        None => Ok(TimelineGroup::default()),
+    };
+    let kinds = body.split_whitespace().map(ToString::to_string).collect();
+    Some(group.map(|group| (group, kinds)))
+}
+
+pub(crate) fn write_timeline(sections: &[TimelineSection], calendar: &Calendar) -> String {
+    let mut output = String::from(r#"<div class="timeline">"#);
+    for section in sections {
+        match (&section.title, &section.article) {
+            (Some(title), Some((kind, id))) => output.push_str(&format!(
+                r#"<h3><a href="/{}/{}">{}</a></h3>"#,
+                escape(kind),
+                escape(id),
+                escape(title)
+            )),
+            (Some(title), None) => output.push_str(&format!("<h3>{}</h3>", escape(title))),
+            _ => (),
+        }
+        output.push_str("<ol>");
+        for entry in &section.entries {
+            output.push_str(&format!(
+                r#"<li><time datetime="{}">{}</time> <a href="/{}/{}">{}</a></li>"#,
+                entry.date,
+                escape(&calendar.format(&entry.date)),
+                escape(&entry.kind),
+                escape(&entry.id),
+                escape(&entry.title)
+            ));
+        }
+        output.push_str("</ol>");
+    }
+    output.push_str("</div>\n");
+    output
+}
@@ -1,3 +1,4 @@
+use crate::lexers::string::unescape;
 use crate::Nodes;
 use neu_parser::{Arena, Children, Node, NodeId, ParseResult};
 
@@ -29,6 +30,7 @@ pub trait Ast {
 pub struct ArticleRef {
     pub identifier: Option<NodeId>,
     pub item_id: Option<NodeId>,
+    pub section: Option<NodeId>,
 }
 
 impl Ast for ArticleRef {
@@ -44,9 +46,12 @@ impl Ast for ArticleRef {
 
         let item_id = children.find_node(Nodes::ArticleItemId).map(get_id);
 
+        let section = children.find_node(Nodes::ArticleSection).map(get_id);
+
         Some(Self {
             identifier,
             item_id,
+            section,
         })
     }
 }
@@ -63,6 +68,13 @@ impl ArticleRef {
         let node = nodes.get(item_id);
         Some(&input[node.span])
     }
+
+    pub fn section<'a>(&self, nodes: &Arena, input: &'a str) -> Option<&'a str> {
+        let section = self.section?;
+        let node = nodes.get(section);
+        // Skip the leading `#`.
+        Some(&input[node.span][1..])
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -125,6 +137,48 @@ impl ArticleItem {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Import {
+    pub id: NodeId,
+    pub path: Option<NodeId>,
+    pub alias: Option<NodeId>,
+}
+
+impl Ast for Import {
+    fn from_syntax(id: NodeId, nodes: &Arena) -> Option<Self> {
+        let node = nodes.get(id);
+        if !node.is(Nodes::Import) {
+            return None;
+        }
+
+        let mut children = Children::new(node.children.iter().copied(), nodes);
+
+        let path = children.find_node(Nodes::ImportPath).map(get_id);
+        let alias = children.find_node(Nodes::Key).map(get_id);
+
+        Some(Self { id, path, alias })
+    }
+}
+
+impl Import {
+    pub fn path(&self, nodes: &Arena, input: &str) -> Option<String> {
+        let path = nodes.get(self.path?);
+        let children = Children::new(path.children.iter().copied(), nodes);
+        Some(
+            children
+                .filter(|(_, node)| node.is(Nodes::StrValue) && !node.is(Nodes::Interpolated))
+                .map(|(_, node)| unescape(&input[node.span]))
+                .collect(),
+        )
+    }
+
+    pub fn alias<'a>(&self, nodes: &Arena, input: &'a str) -> Option<&'a str> {
+        let alias = self.alias?;
+        let node = nodes.get(alias);
+        Some(&input[node.span])
+    }
+}
+
 fn get_id((item_id, _node): (NodeId, &Node)) -> NodeId {
     item_id
 }
@@ -1,7 +1,8 @@
-use crate::ast::{ArticleItem, Ast};
+use crate::ast::{ArticleItem, Ast, Import};
 use neu_canceled::Canceled;
 use neu_parser::{NodeId, ParseResult, State};
 use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
@@ -46,6 +47,9 @@ pub trait Parser: salsa::Database {
 
     fn parse_neu_syntax(&self, path: FileId) -> Arc<ParseResult>;
     fn parse_all_neu(&self) -> Vec<(FileId, NodeId)>;
+
+    fn resolve_import(&self, file: FileId, import: String) -> Option<FileId>;
+    fn file_imports(&self, file: FileId) -> Arc<Vec<(NodeId, Option<FileId>)>>;
 }
 
 fn parse_syntax(db: &dyn Parser, file: FileId) -> Arc<ParseResult> {
@@ -93,8 +97,10 @@ fn parse_all_neu(db: &dyn Parser) -> Vec<(FileId, NodeId)> {
 
 fn parse_all_mds(db: &dyn Parser) -> Vec<(Kind, ArticleId, FileId, ArticleItem)> {
     Canceled::cancel_if(db.salsa_runtime());
-    db.all_mds()
-        .iter()
+    // Sorted by path, so `find_md` picks the same article every time when ids collide.
+    let mut mds = db.all_mds().iter().copied().collect::<Vec<_>>();
+    mds.sort_by_key(|md| db.lookup_file_id(*md).0);
+    mds.iter()
         .flat_map(|md| {
             let input = db.input(*md);
             let input = input.as_str();
@@ -124,3 +130,74 @@ fn find_md(db: &dyn Parser, lkind: Kind, lid: ArticleId) -> Option<(FileId, Arti
         .find(|(kind, id, _path, _item)| &lkind == kind && &lid == id)
         .map(|(_kind, _id, path, item)| (path, item))
 }
+
+fn resolve_import(db: &dyn Parser, file: FileId, import: String) -> Option<FileId> {
+    Canceled::cancel_if(db.salsa_runtime());
+    let (importer, _) = db.lookup_file_id(file);
+    let base = Path::new(&importer)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let resolved = normalize_path(&base.join(import)).display().to_string();
+
+    db.all_neu()
+        .iter()
+        .copied()
+        .find(|neu| db.lookup_file_id(*neu).0 == resolved)
+}
+
+fn file_imports(db: &dyn Parser, file: FileId) -> Arc<Vec<(NodeId, Option<FileId>)>> {
+    Canceled::cancel_if(db.salsa_runtime());
+    let input = db.input(file);
+    let parsed = db.parse_syntax(file);
+
+    let imports = parsed
+        .arena
+        .enumerate()
+        .filter_map(|(id, _)| Import::from_syntax(id, &parsed.arena))
+        .map(|import| {
+            let target = import
+                .path(&parsed.arena, &input)
+                .and_then(|path| db.resolve_import(file, path));
+            (import.id, target)
+        })
+        .collect();
+
+    Arc::new(imports)
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// Replaces every input with in-memory files, `.neu` files become modules and the rest articles.
+pub fn load_files(db: &mut dyn Parser, files: &[(&str, &str)]) {
+    let mut mds = HashSet::new();
+    let mut neu = HashSet::new();
+    for (name, input) in files {
+        let kind = if name.ends_with(".neu") {
+            FileKind::Neu
+        } else {
+            FileKind::Md
+        };
+        let file = db.file_id((name.to_string(), kind));
+        db.set_input(file, Arc::new(input.to_string()));
+        match kind {
+            FileKind::Md => mds.insert(file),
+            FileKind::Neu => neu.insert(file),
+        };
+    }
+    db.set_all_mds(Arc::new(mds));
+    db.set_all_neu(Arc::new(neu));
+}
@@ -9,6 +9,9 @@ pub enum Token {
     #[display(fmt = "`++ end ++`")]
     PlusPlusEnd,
 
+    #[display(fmt = "`++ secret ++`")]
+    PlusPlusSecret,
+
     #[display(fmt = "`++`")]
     PlusPlus,
 
@@ -43,6 +46,10 @@ impl TokenKind for Token {
             return Some((Token::PlusPlusEnd, input.chomp(9)));
         }
 
+        if i.starts_with("++ secret ++") {
+            return Some((Token::PlusPlusSecret, input.chomp(12)));
+        }
+
         if i.starts_with("++") {
             return Some((Token::PlusPlus, input.chomp(2)));
         }
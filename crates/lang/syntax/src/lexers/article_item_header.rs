@@ -24,6 +24,9 @@ pub enum Token {
     #[display(fmt = "item id")]
     ItemId,
 
+    #[display(fmt = "section")]
+    Section,
+
     #[display(fmt = "error")]
     Error,
 }
@@ -73,6 +76,17 @@ impl TokenKind for Token {
             return Some((Token::Colon, input.chomp(1)));
         }
 
+        if peeked == '#' {
+            let rest = i
+                .chars()
+                .skip(1)
+                .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                .count();
+            if rest > 0 {
+                return Some((Token::Section, input.chomp(rest + 1)));
+            }
+        }
+
         if i.chars().take(8).all(|c| c.is_ascii_hexdigit() || c == '_') {
             let rest = i.chars().take(8).count();
 
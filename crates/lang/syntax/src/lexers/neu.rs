@@ -19,6 +19,9 @@ pub enum Token {
     #[display(fmt = "number")]
     Number,
 
+    #[display(fmt = "date")]
+    Date,
+
     #[display(fmt = "`true`")]
     True,
 
@@ -43,6 +46,21 @@ pub enum Token {
     #[display(fmt = "`==`")]
     OpDEqual,
 
+    #[display(fmt = "`!=`")]
+    OpNEqual,
+
+    #[display(fmt = "`<`")]
+    OpLess,
+
+    #[display(fmt = "`<=`")]
+    OpLessEq,
+
+    #[display(fmt = "`>`")]
+    OpGreater,
+
+    #[display(fmt = "`>=`")]
+    OpGreaterEq,
+
     #[display(fmt = "`=`")]
     OpAssign,
 
@@ -78,6 +96,39 @@ pub enum Token {
 
     #[display(fmt = "`.`")]
     OpDot,
+
+    #[display(fmt = "`import`")]
+    Import,
+
+    #[display(fmt = "`as`")]
+    As,
+
+    #[display(fmt = "`enum`")]
+    Enum,
+
+    #[display(fmt = "`match`")]
+    Match,
+
+    #[display(fmt = "`=>`")]
+    FatArrow,
+
+    #[display(fmt = "`_`")]
+    Underscore,
+
+    #[display(fmt = "`null`")]
+    Null,
+
+    #[display(fmt = "`?.`")]
+    OpSafeDot,
+
+    #[display(fmt = "`??`")]
+    OpCoalesce,
+
+    #[display(fmt = "`let`")]
+    Let,
+
+    #[display(fmt = "`in`")]
+    In,
 }
 
 pub type Lexer<T = Token> = neu_parser::Lexer<T>;
@@ -113,6 +164,11 @@ impl TokenKind for Token {
 
             return Some((Token::Whitespace, input.chomp(rest)));
         }
+        if peeked == '@' {
+            if let Some(rest) = date_len(&i[1..]) {
+                return Some((Token::Date, input.chomp(rest + 1)));
+            }
+        }
         if peeked.is_ascii_digit() {
             let rest = i.chars().take_while(|c| c.is_ascii_digit()).count();
 
@@ -144,6 +200,30 @@ impl TokenKind for Token {
             return Some((Token::OpDEqual, input.chomp(2)));
         }
 
+        if i.starts_with("!=") {
+            return Some((Token::OpNEqual, input.chomp(2)));
+        }
+
+        if i.starts_with("<=") {
+            return Some((Token::OpLessEq, input.chomp(2)));
+        }
+
+        if i.starts_with(">=") {
+            return Some((Token::OpGreaterEq, input.chomp(2)));
+        }
+
+        if i.starts_with("?.") {
+            return Some((Token::OpSafeDot, input.chomp(2)));
+        }
+
+        if i.starts_with("??") {
+            return Some((Token::OpCoalesce, input.chomp(2)));
+        }
+
+        if i.starts_with("=>") {
+            return Some((Token::FatArrow, input.chomp(2)));
+        }
+
         if i.starts_with("true") {
             return Some((Token::True, input.chomp(4)));
         }
@@ -190,6 +270,12 @@ impl TokenKind for Token {
         if peeked == '=' {
             return Some((Token::OpAssign, input.chomp(1)));
         }
+        if peeked == '<' {
+            return Some((Token::OpLess, input.chomp(1)));
+        }
+        if peeked == '>' {
+            return Some((Token::OpGreater, input.chomp(1)));
+        }
         if peeked == '.' {
             return Some((Token::OpDot, input.chomp(1)));
         }
@@ -230,14 +316,66 @@ impl TokenKind for Token {
             return Some((Token::DoubleQuote, input.chomp(1)));
         }
 
-        if peeked.is_ascii_alphabetic() {
+        if peeked.is_ascii_alphabetic() || peeked == '_' {
             let rest = i
                 .chars()
                 .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
                 .count();
-            return Some((Token::Identifier, input.chomp(rest)));
+            // Keywords are plain names after a `.` and before `=`, as in `a.match` or `in = 1`.
+            let is_space = |c: char| c == ' ' || c == '\t';
+            let before = input.range_span(TextRange::up_to(input.cursor()));
+            let after = i[rest..].trim_start_matches(is_space);
+            let is_name = before.trim_end_matches(is_space).ends_with('.')
+                || (after.starts_with('=') && !after.starts_with("==") && !after.starts_with("=>"));
+            let token = match &i[..rest] {
+                _ if is_name => Token::Identifier,
+                "import" => Token::Import,
+                "as" => Token::As,
+                "enum" => Token::Enum,
+                "match" => Token::Match,
+                "_" => Token::Underscore,
+                "null" => Token::Null,
+                "let" => Token::Let,
+                "in" => Token::In,
+                _ => Token::Identifier,
+            };
+            return Some((token, input.chomp(rest)));
         }
 
         Some((Token::Error, input.chomp(1)))
     }
 }
+
+// `1372-05-12` or `1372-05-12T14:30`, years before the calendar epoch are negative.
+fn date_len(i: &str) -> Option<usize> {
+    let bytes = i.as_bytes();
+    let digits = |from: usize| {
+        let count = bytes
+            .iter()
+            .skip(from)
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if count > 0 {
+            Some(from + count)
+        } else {
+            None
+        }
+    };
+
+    let start = if i.starts_with('-') { 1 } else { 0 };
+    let mut len = digits(start)?;
+    for _ in 0..2 {
+        if bytes.get(len) != Some(&b'-') {
+            return None;
+        }
+        len = digits(len + 1)?;
+    }
+    // The whole time stays in the token, so a malformed one like `T14` is reported by the date.
+    if bytes.get(len) == Some(&b'T') {
+        len += 1 + bytes[len + 1..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit() || **b == b':')
+            .count();
+    }
+    Some(len)
+}
@@ -36,6 +36,10 @@ impl TokenKind for Token {
         if i.is_empty() {
             return None;
         }
+        if i.starts_with('\\') && i.len() > 1 {
+            return Some((Token::Text, input.chomp(2)));
+        }
+
         if i.starts_with('"') {
             return Some((Token::Close, input.chomp(1)));
         }
@@ -51,3 +55,18 @@ impl TokenKind for Token {
         Some((Token::Text, input.chomp(1)))
     }
 }
+
+// The lexer keeps `\"`, `\\` and `\${` as written, other backslashes are left alone.
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('"')) | ('\\', Some('\\')) | ('\\', Some('$')) => {
+                unescaped.extend(chars.next())
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
@@ -6,7 +6,9 @@ nodes! {
         Value,
         Parens,
         Number,
+        Date,
         Boolean,
+        Null,
         String,
         Interpolated,
 
@@ -21,13 +23,37 @@ nodes! {
         Key,
 
         Array,
-        IdentPath
+        IdentPath,
+
+        Import,
+        ImportPath,
+
+        Call,
+
+        Enum,
+        EnumName,
+        EnumVariant,
+        EnumField,
+
+        Match,
+        MatchArm,
+        Pattern,
+        PatternPath,
+        PatternBinding,
+        PatternLiteral,
+        PatternTuple,
+        Wildcard,
+
+        Tuple,
+        Let
     },
     Item {
         ArticleItem,
         ArticleItemId,
+        ArticleSection,
         ArticleBody,
-        ArticleRef
+        ArticleRef,
+        Secret
     },
     Markdown {
         Markdown,
@@ -66,6 +92,7 @@ nodes! {
 
         Md_CodeBlock,
         Md_CodeBlockLang,
+        Md_CodeShow,
 
         Md_H1,
         Md_H2,
@@ -74,6 +101,32 @@ nodes! {
         Md_H5,
         Md_H6,
 
+        Md_Strikethrough,
+
+        Md_Table,
+        Md_TableHead,
+        Md_TableRow,
+        Md_TableCell,
+        Md_TableHeadCell,
+        Md_AlignLeft,
+        Md_AlignCenter,
+        Md_AlignRight,
+
+        Md_FootnoteDefinition,
+        Md_FootnoteReference,
+        Md_FootnoteLabel,
+
+        Md_TaskListMarker,
+        Md_Checked,
+
+        Md_Interpolation,
+
+        Md_WikiLink,
+        Md_WikiSecret,
+        Md_WikiKind,
+        Md_WikiId,
+        Md_WikiLabel,
+
         Md_Text,
         Md_Html
     }
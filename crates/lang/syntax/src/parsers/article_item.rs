@@ -153,10 +153,14 @@ fn item_body(ends: bool) -> impl Parser<BodyToken> {
                 Some(BodyToken::OpenBl) => {
                     builder.parse(item_bl());
                 }
+                Some(BodyToken::PlusPlusSecret) => {
+                    builder.parse(secret());
+                }
                 Some(_) => {
                     builder.parse(expected(&[
                         BodyToken::Text,
                         BodyToken::PlusPlus,
+                        BodyToken::PlusPlusSecret,
                         BodyToken::OpenBl,
                     ]));
                 }
@@ -165,6 +169,16 @@ fn item_body(ends: bool) -> impl Parser<BodyToken> {
     })
 }
 
+// GM-only content, left out of player builds.
+fn secret() -> impl Parser<BodyToken> {
+    node(|builder| {
+        builder.name(Nodes::Secret);
+        builder.parse(token(BodyToken::PlusPlusSecret));
+        builder.parse(item_body(true));
+        builder.parse(token(BodyToken::PlusPlusEnd));
+    })
+}
+
 fn item_bl() -> impl Parser<BodyToken> {
     node(|builder| {
         builder.name(Nodes::ArticleRef);
@@ -181,6 +195,9 @@ fn item_bl() -> impl Parser<BodyToken> {
                 ));
                 builder.parse(token(HeaderToken::Colon));
                 builder.parse(named(token(HeaderToken::ItemId), Nodes::ArticleItemId));
+                if let Some(HeaderToken::Section) = builder.peek_token() {
+                    builder.parse(named(token(HeaderToken::Section), Nodes::ArticleSection));
+                }
                 builder.parse(req_trivia(HeaderToken::InlineWhitespace));
             }),
         );
@@ -189,12 +206,7 @@ fn item_bl() -> impl Parser<BodyToken> {
 }
 
 fn struct_key_val() -> impl Parser<NeuToken> {
-    node(|builder| {
-        builder.name(Nodes::Virtual);
-        builder.parse(neu::strukt_key());
-        builder.parse(token(NeuToken::OpAssign));
-        builder.parse(neu::value());
-    })
+    neu::strukt_field()
 }
 
 fn req_trivia(tok: HeaderToken) -> impl Parser<HeaderToken> {
@@ -1,15 +1,15 @@
 use crate::Nodes;
 use crate::{lexers::md_string::Token as MdStrToken, lexers::neu::Token as NeuToken, HashCount};
 use neu_parser::*;
-use pulldown_cmark::{CodeBlockKind, CowStr, Event, LinkType, Tag};
+use pulldown_cmark::{Alignment, CodeBlockKind, CowStr, Event, LinkType, Options, Tag};
 use std::convert::TryFrom;
 use text_size::{TextLen, TextRange, TextSize};
 
 fn offset(a: &str, orig: &str) -> Option<usize> {
-    let a = a.as_ptr() as usize;
-    let orig = orig.as_ptr() as usize;
-    if a > orig {
-        Some(a - orig)
+    let start = a.as_ptr() as usize;
+    let orig_start = orig.as_ptr() as usize;
+    if start >= orig_start && start + a.len() <= orig_start + orig.len() {
+        Some(start - orig_start)
     } else {
         None
     }
@@ -18,15 +18,254 @@ fn offset(a: &str, orig: &str) -> Option<usize> {
 fn get_range<'a>(str: &'a str, cow: &CowStr<'a>, range: TextRange, from: TextSize) -> TextRange {
     match cow {
         CowStr::Boxed(_) => unreachable!("It should be never owned"),
-        CowStr::Borrowed(s) => {
-            let offset = offset(s, str).unwrap_or(0);
-            let offset = TextSize::try_from(offset).unwrap();
-            TextRange::at(offset + from, s.text_len())
-        }
+        // Entities like `&amp;` are borrowed from a static table, not from the input.
+        CowStr::Borrowed(s) => match offset(s, str) {
+            Some(offset) => {
+                let offset = TextSize::try_from(offset).unwrap();
+                TextRange::at(offset + from, s.text_len())
+            }
+            None if s.is_empty() => TextRange::empty(from),
+            None => range,
+        },
         _ => range,
     }
 }
 
+fn slice(str: &str, range: TextRange, from: TextSize) -> &str {
+    let start = usize::from(range.start() - from);
+    let end = usize::from(range.end() - from);
+    &str[start..end]
+}
+
+fn interpolations(text: &str) -> (Vec<(usize, usize)>, bool) {
+    let mut found = vec![];
+    let mut i = 0;
+    while let Some(offset) = text[i..].find("${") {
+        let start = i + offset;
+        let mut depth = 0;
+        let mut end = None;
+        for (j, c) in text[start + 1..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(start + 1 + j + 1);
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+        match end {
+            Some(end) => {
+                found.push((start, end));
+                i = end;
+            }
+            None => return (found, false),
+        }
+    }
+    (found, true)
+}
+
+// `[[kind:id]]` or `[[kind:id|label]]`, `[[secret:kind:id]]` hides the link from players.
+fn wiki_link(text: &str) -> Option<(usize, usize, usize, Option<usize>)> {
+    if text.contains(|c| c == '\n' || c == '[' || c == ']') {
+        return None;
+    }
+    let target = text.find('|').unwrap_or_else(|| text.len());
+    let secret = if text.starts_with("secret:") { 7 } else { 0 };
+    let colon = secret + text[secret..target].find(':')?;
+    let kind = &text[secret..colon];
+    let id = &text[colon + 1..target];
+    let valid = !kind.is_empty()
+        && kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return None;
+    }
+    let label = if target < text.len() {
+        Some(target + 1)
+    } else {
+        None
+    };
+    Some((secret, colon, target, label))
+}
+
+fn wiki_links(text: &str) -> Vec<(usize, usize)> {
+    let mut found = vec![];
+    let mut i = 0;
+    while let Some(offset) = text[i..].find("[[") {
+        let start = i + offset;
+        let inner = &text[start + 2..];
+        match inner.find("]]") {
+            Some(len) if wiki_link(&inner[..len]).is_some() => {
+                let end = start + 2 + len + 2;
+                found.push((start, end));
+                i = end;
+            }
+            _ => i = start + 1,
+        }
+    }
+    found
+}
+
+// pulldown-cmark splits unmatched brackets into separate text events.
+fn open_wiki_link(text: &str) -> bool {
+    match text.rfind("[[") {
+        Some(start) => !text[start..].contains("]]"),
+        None => text.ends_with('['),
+    }
+}
+
+fn is_incomplete(text: &str) -> bool {
+    !interpolations(text).1 || open_wiki_link(text)
+}
+
+fn parse_interpolated<Token>(builder: &mut NodeBuilder<Token>, range: TextRange)
+where
+    Token: TokenKind,
+    Token::Extra: From<HashCount> + Into<HashCount>,
+{
+    let ctx = Context::default();
+    builder.parse_mode(
+        &ctx,
+        node(move |builder: &mut NodeBuilder<NeuToken>| {
+            let saved = builder.state_mut().lexer_mut().input().clone();
+
+            builder.state_mut().lexer_mut().input_mut().set_range(range);
+            builder.name(Nodes::Virtual);
+            builder.name(Nodes::Interpolated);
+            builder.parse(crate::parsers::neu::parser());
+
+            *builder.state_mut().lexer_mut().input_mut() = saved;
+        }),
+    );
+}
+
+fn parse_text<Token>(str: &str, builder: &mut NodeBuilder<Token>, range: TextRange, from: TextSize)
+where
+    Token: TokenKind,
+    Token::Extra: From<HashCount> + Into<HashCount>,
+{
+    let text_node = |range: TextRange| {
+        node(move |builder: &mut NodeBuilder<Token>| {
+            builder.name(Nodes::Md_Text);
+            builder.set_span(range);
+        })
+    };
+    let at = |offset: usize| range.start() + TextSize::try_from(offset).unwrap();
+    let plain = |builder: &mut NodeBuilder<Token>, start: usize, end: usize| {
+        let text = slice(str, TextRange::new(at(start), at(end)), from);
+        let mut rest = start;
+        for (link_start, link_end) in wiki_links(text) {
+            let (link_start, link_end) = (start + link_start, start + link_end);
+            if link_start > rest {
+                builder.parse(text_node(TextRange::new(at(rest), at(link_start))));
+            }
+            let inner = link_start + 2;
+            let link = slice(str, TextRange::new(at(inner), at(link_end - 2)), from);
+            let (secret, colon, target, label) = wiki_link(link).expect("wiki link");
+            builder.parse(node(move |builder: &mut NodeBuilder<Token>| {
+                builder.name(Nodes::Md_WikiLink);
+                builder.set_span(TextRange::new(at(link_start), at(link_end)));
+                let part = |name: Name, start: usize, end: usize| {
+                    node(move |builder: &mut NodeBuilder<Token>| {
+                        builder.name(name);
+                        builder.set_span(TextRange::new(at(inner + start), at(inner + end)));
+                    })
+                };
+                if secret > 0 {
+                    builder.parse(part(Nodes::Md_WikiSecret, 0, secret - 1));
+                }
+                builder.parse(part(Nodes::Md_WikiKind, secret, colon));
+                builder.parse(part(Nodes::Md_WikiId, colon + 1, target));
+                if let Some(label) = label {
+                    builder.parse(part(Nodes::Md_WikiLabel, label, link.len()));
+                }
+            }));
+            rest = link_end;
+        }
+        if rest < end {
+            builder.parse(text_node(TextRange::new(at(rest), at(end))));
+        }
+    };
+
+    let (found, _) = interpolations(slice(str, range, from));
+    let mut rest = 0;
+    for (start, end) in found {
+        if start > rest {
+            plain(builder, rest, start);
+        }
+        let outer = TextRange::new(at(start), at(end));
+        let inner = TextRange::new(at(start + 2), at(end - 1));
+        builder.parse(node(move |builder: &mut NodeBuilder<Token>| {
+            builder.name(Nodes::Md_Interpolation);
+            builder.set_span(outer);
+            parse_interpolated(builder, inner);
+        }));
+        rest = end;
+    }
+    let len = usize::from(range.len());
+    if rest == 0 && len == 0 {
+        builder.parse(text_node(range));
+    } else if rest < len {
+        plain(builder, rest, len);
+    }
+}
+
+fn parse_table_row<'a, Token>(
+    span: TextRange,
+    tag: &Tag,
+    alignments: &[Alignment],
+    str: &'a str,
+    builder: &mut NodeBuilder<Token>,
+    events: &mut impl PeekableIterator<Item = (Event<'a>, TextRange)>,
+    from: TextSize,
+) where
+    Token: TokenKind,
+    Token::Extra: From<HashCount> + Into<HashCount>,
+{
+    builder.set_span(span);
+    let head = matches!(tag, Tag::TableHead);
+    builder.name(if head {
+        Nodes::Md_TableHead
+    } else {
+        Nodes::Md_TableRow
+    });
+
+    let mut column = 0;
+    while let Some((peeked, _)) = events.peek() {
+        if let Event::End(_) = peeked {
+            events.next();
+            break;
+        }
+        if let Some((Event::Start(Tag::TableCell), cell_span)) = events.next() {
+            let alignment = alignments.get(column).cloned();
+            column += 1;
+            let events = &mut *events;
+            builder.parse(node_mut(move |builder| {
+                parse_start(cell_span, &Tag::TableCell, str, builder, events, from);
+                if head {
+                    builder.name(Nodes::Md_TableHeadCell);
+                }
+                let align = match alignment {
+                    Some(Alignment::Left) => Some(Nodes::Md_AlignLeft),
+                    Some(Alignment::Center) => Some(Nodes::Md_AlignCenter),
+                    Some(Alignment::Right) => Some(Nodes::Md_AlignRight),
+                    _ => None,
+                };
+                if let Some(align) = align {
+                    builder.name(align);
+                }
+            }));
+        }
+    }
+}
+
 fn parse_start<'a, Token>(
     span: TextRange,
     tag: &Tag,
@@ -55,7 +294,10 @@ fn parse_start<'a, Token>(
                 CodeBlockKind::Indented => "",
                 CodeBlockKind::Fenced(lang) => lang.as_ref(),
             };
-            if lang_str == "neu" || lang_str == "" {
+            let mut info = lang_str.split_whitespace();
+            let is_neu = info.next().map(|lang| lang == "neu").unwrap_or(true);
+            let show = info.any(|option| option == "show");
+            if is_neu {
                 while let Some((peeked, peeked_range)) = events.peek() {
                     if let Event::End(_) = peeked {
                         events.next();
@@ -94,6 +336,9 @@ fn parse_start<'a, Token>(
                             builder.state_mut().lexer_mut().input_mut().set_range(range);
                             builder.name(Nodes::Virtual);
                             builder.name(Nodes::Interpolated);
+                            if show {
+                                builder.name(Nodes::Md_CodeShow);
+                            }
                             builder.parse(crate::parsers::neu::parser());
 
                             *builder.state_mut().lexer_mut().input_mut() = saved;
@@ -109,13 +354,35 @@ fn parse_start<'a, Token>(
             Nodes::Md_OrderedList
         }
         Tag::Item => Nodes::Md_ListItem,
-        Tag::FootnoteDefinition(_) => todo!("FootnoteDefinition"),
-        Tag::Table(_) => todo!("Table"),
-        Tag::TableHead => todo!("TableHead"),
-        Tag::TableRow => todo!("TableRow"),
-        Tag::TableCell => todo!("TableCell"),
+        Tag::FootnoteDefinition(label) => {
+            let label_range = get_range(str, label, span, from);
+            builder.parse(node(|builder| {
+                builder.name(Nodes::Md_FootnoteLabel);
+                builder.set_span(label_range);
+            }));
+            Nodes::Md_FootnoteDefinition
+        }
+        Tag::Table(alignments) => {
+            builder.name(Nodes::Md_Table);
+            while let Some((peeked, _)) = events.peek() {
+                if let Event::End(_) = peeked {
+                    events.next();
+                    break;
+                }
+                if let Some((Event::Start(row), row_span)) = events.next() {
+                    let events = &mut *events;
+                    builder.parse(node_mut(move |builder| {
+                        parse_table_row(row_span, &row, alignments, str, builder, events, from);
+                    }));
+                }
+            }
+            return;
+        }
+        Tag::TableHead => Nodes::Md_TableHead,
+        Tag::TableRow => Nodes::Md_TableRow,
+        Tag::TableCell => Nodes::Md_TableCell,
         Tag::Strong => Nodes::Md_Strong,
-        Tag::Strikethrough => todo!("Strikethrough"),
+        Tag::Strikethrough => Nodes::Md_Strikethrough,
         Tag::Link(link_type, url, title) => {
             let url_range = get_range(str, url, span, from);
             let title_range = get_range(str, title, span, from);
@@ -199,28 +466,23 @@ fn parse_event<'a, Token>(
             }
         }
         Event::Text(cow) => {
-            let range = get_range(str, &cow, span, from);
-            builder.parse(node(move |builder| {
-                builder.name(Nodes::Md_Text);
-                builder.set_span(range);
-            }));
+            let mut range = get_range(str, &cow, span, from);
+            while is_incomplete(slice(str, range, from)) {
+                let next_range = match events.peek() {
+                    Some((Event::Text(next), next_span)) => get_range(str, next, *next_span, from),
+                    _ => break,
+                };
+                if next_range.start() != range.end() {
+                    break;
+                }
+                range = range.cover(next_range);
+                events.next();
+            }
+            parse_text(str, builder, range, from);
         }
         Event::Code(cow) => {
             let range = get_range(str, &cow, span, from);
-            let ctx = Context::default();
-            builder.parse_mode(
-                &ctx,
-                node(move |builder: &mut NodeBuilder<NeuToken>| {
-                    let saved = builder.state_mut().lexer_mut().input().clone();
-
-                    builder.state_mut().lexer_mut().input_mut().set_range(range);
-                    builder.name(Nodes::Virtual);
-                    builder.name(Nodes::Interpolated);
-                    builder.parse(crate::parsers::neu::parser());
-
-                    *builder.state_mut().lexer_mut().input_mut() = saved;
-                }),
-            );
+            parse_interpolated(builder, range);
         }
         Event::Html(cow) => {
             let range = get_range(str, &cow, span, from);
@@ -229,7 +491,17 @@ fn parse_event<'a, Token>(
                 builder.set_span(range);
             }));
         }
-        Event::FootnoteReference(_span) => todo!("FootnoteReference"),
+        Event::FootnoteReference(label) => {
+            let label_range = get_range(str, &label, span, from);
+            builder.parse(node(move |builder| {
+                builder.name(Nodes::Md_FootnoteReference);
+                builder.set_span(span);
+                builder.parse(node(move |builder| {
+                    builder.name(Nodes::Md_FootnoteLabel);
+                    builder.set_span(label_range);
+                }));
+            }));
+        }
         Event::SoftBreak => {
             builder.parse(node(|builder| {
                 builder.name(Nodes::Md_SoftBreak);
@@ -246,7 +518,13 @@ fn parse_event<'a, Token>(
                 builder.set_span(span);
             }));
         }
-        Event::TaskListMarker(_bool) => todo!("TaskListMarker"),
+        Event::TaskListMarker(checked) => builder.parse(node(move |builder| {
+            builder.name(Nodes::Md_TaskListMarker);
+            if checked {
+                builder.name(Nodes::Md_Checked);
+            }
+            builder.set_span(span);
+        })),
         _ => {}
     }
 }
@@ -260,7 +538,11 @@ where
     let span = next.span;
     let str = i.range_span(span);
     let from = span.start();
-    let md_parser = pulldown_cmark::Parser::new(str)
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let md_parser = pulldown_cmark::Parser::new_ext(str, options)
         .into_offset_iter()
         .map(|(event, range)| {
             let range = TextRange::new(
@@ -15,17 +15,32 @@ pub fn parser() -> impl Parser<Token> {
             leading_trivia: Some(&leading_trivia),
             trailing_trivia: Some(&trailing_trivia),
         };
+        while let Some(Token::Import) = builder.peek_token_with(&ctx) {
+            builder.parse_ctx(&ctx, import());
+        }
         builder.parse_ctx(&ctx, value());
         builder.parse_ctx(&ctx, token(None));
     })
 }
 
+pub(crate) fn import() -> impl Parser<Token> {
+    node(|builder| {
+        builder.name(Nodes::Import);
+        builder.name(Nodes::Value);
+        builder.parse(token(Token::Import));
+        builder.parse(named(string(), Nodes::ImportPath));
+        builder.parse(token(Token::As));
+        builder.parse(strukt_key());
+    })
+}
+
 pub(crate) fn value() -> impl Parser<Token> + Clone {
     let next = |state: &mut State<_>, ctx: &Context<_>| left_value().parse(state, ctx);
     Pratt::new(
         next,
         |token| match token {
             Some(Token::OpDot) => Some((Assoc::Left, 100)),
+            Some(Token::OpSafeDot) => Some((Assoc::Left, 100)),
 
             Some(Token::OpStar) => Some((Assoc::Left, 20)),
             Some(Token::OpSlash) => Some((Assoc::Left, 20)),
@@ -33,12 +48,20 @@ pub(crate) fn value() -> impl Parser<Token> + Clone {
             Some(Token::OpMinus) => Some((Assoc::Left, 10)),
             Some(Token::OpPlus) => Some((Assoc::Left, 10)),
 
+            Some(Token::OpCoalesce) => Some((Assoc::Left, 7)),
+
+            Some(Token::OpLess) => Some((Assoc::Left, 5)),
+            Some(Token::OpLessEq) => Some((Assoc::Left, 5)),
+            Some(Token::OpGreater) => Some((Assoc::Left, 5)),
+            Some(Token::OpGreaterEq) => Some((Assoc::Left, 5)),
+
             Some(Token::OpDEqual) => Some((Assoc::Left, 1)),
+            Some(Token::OpNEqual) => Some((Assoc::Left, 1)),
             _ => None,
         },
         |builder, op_token| {
             match op_token {
-                Some(Token::OpDot) => {
+                Some(Token::OpDot) | Some(Token::OpSafeDot) => {
                     builder.name(Nodes::IdentPath);
                 }
                 _ => {
@@ -71,7 +94,9 @@ fn left_value() -> impl Parser<Token> {
         builder.name(Nodes::Value);
         match builder.peek_token() {
             Some(Token::Number) => builder.parse(number()),
+            Some(Token::Date) => builder.parse(date()),
             Some(Token::True) | Some(Token::False) => builder.parse(boolean()),
+            Some(Token::Null) => builder.parse(null()),
             Some(Token::OpMinus) | Some(Token::OpBang) | Some(Token::OpDot) => {
                 builder.parse(unary())
             }
@@ -79,18 +104,34 @@ fn left_value() -> impl Parser<Token> {
             Some(Token::DoubleQuote) => builder.parse(string()),
             Some(Token::OpenC) => builder.parse(strukt()),
             Some(Token::OpenB) => builder.parse(array()),
-            Some(Token::Identifier) => builder.parse(identifier()),
-            Some(Token::OpenP) => builder.parse(node(|builder| {
-                builder.name(Nodes::Parens);
-                builder.parse(token(Token::OpenP));
-                builder.parse(value());
-                builder.parse(token(Token::CloseP));
-            })),
+            Some(Token::Identifier) => builder.parse(identifier_or_call()),
+            Some(Token::Enum) => builder.parse(enum_decl()),
+            Some(Token::Match) => builder.parse(match_expr()),
+            Some(Token::Let) => builder.parse(let_expr()),
+            Some(Token::OpenP) => builder.parse(parens_or_tuple()),
             _ => builder.parse(expected(VALUE_TOKENS)),
         };
     })
 }
 
+fn parens_or_tuple() -> impl Parser<Token> {
+    node(|builder| {
+        builder.parse(token(Token::OpenP));
+        builder.parse(value());
+        match builder.peek_token() {
+            Some(Token::Comma) => {
+                builder.name(Nodes::Tuple);
+                builder.parse(token(Token::Comma));
+                builder.parse(separated(value(), Token::Comma, Token::CloseP, true));
+            }
+            _ => {
+                builder.name(Nodes::Parens);
+            }
+        }
+        builder.parse(token(Token::CloseP));
+    })
+}
+
 fn array() -> impl Parser<Token> {
     node(|builder| {
         builder.name(Nodes::Array);
@@ -104,29 +145,198 @@ fn identifier() -> impl Parser<Token> {
     named(token(Token::Identifier), Nodes::Identifier)
 }
 
+fn identifier_or_call() -> impl Parser<Token> {
+    node(|builder| {
+        builder.parse(identifier());
+        match builder.peek_token() {
+            Some(Token::OpenP) => {
+                builder.name(Nodes::Call);
+                builder.parse(token(Token::OpenP));
+                builder.parse(separated(value(), Token::Comma, Token::CloseP, true));
+                builder.parse(token(Token::CloseP));
+            }
+            _ => {
+                builder.name(Nodes::Virtual);
+            }
+        }
+    })
+}
+
 pub(crate) fn strukt_key() -> impl Parser<Token> {
     named(identifier(), Nodes::Key)
 }
 
-fn strukt() -> impl Parser<Token> {
+pub(crate) fn strukt_field() -> impl Parser<Token> + Clone {
     node(|builder| {
-        builder.name(Nodes::Struct);
-        builder.parse(token(Token::OpenC));
-        builder.parse(separated(
-            node(|builder| {
-                builder.name(Nodes::Virtual);
+        builder.name(Nodes::Virtual);
+        match builder.peek_token() {
+            Some(Token::Import) => builder.parse(import()),
+            _ => {
                 builder.parse(strukt_key());
                 builder.parse(token(Token::OpAssign));
                 builder.parse(value());
-            }),
-            Token::Comma,
-            Token::CloseC,
-            true,
-        ));
+            }
+        }
+    })
+}
+
+fn strukt() -> impl Parser<Token> {
+    node(|builder| {
+        builder.name(Nodes::Struct);
+        builder.parse(token(Token::OpenC));
+        builder.parse(separated(strukt_field(), Token::Comma, Token::CloseC, true));
+        builder.parse(token(Token::CloseC));
+    })
+}
+
+fn enum_decl() -> impl Parser<Token> {
+    node(|builder| {
+        builder.name(Nodes::Enum);
+        builder.parse(token(Token::Enum));
+        if let Some(Token::Identifier) = builder.peek_token() {
+            builder.parse(named(identifier(), Nodes::EnumName));
+        }
+        builder.parse(token(Token::OpenC));
+        builder.parse(separated(enum_variant(), Token::Comma, Token::CloseC, true));
+        builder.parse(token(Token::CloseC));
+    })
+}
+
+fn enum_variant() -> impl Parser<Token> + Clone {
+    node(|builder| {
+        builder.name(Nodes::EnumVariant);
+        builder.parse(strukt_key());
+        if let Some(Token::OpenP) = builder.peek_token() {
+            builder.parse(token(Token::OpenP));
+            builder.parse(separated(enum_field(), Token::Comma, Token::CloseP, true));
+            builder.parse(token(Token::CloseP));
+        }
+    })
+}
+
+fn enum_field() -> impl Parser<Token> + Clone {
+    node(|builder| {
+        builder.name(Nodes::Virtual);
+        builder.name(Nodes::EnumField);
+        builder.parse(identifier());
+    })
+}
+
+fn match_expr() -> impl Parser<Token> {
+    node(|builder| {
+        builder.name(Nodes::Match);
+        builder.parse(token(Token::Match));
+        builder.parse(value());
+        builder.parse(token(Token::OpenC));
+        builder.parse(separated(match_arm(), Token::Comma, Token::CloseC, true));
         builder.parse(token(Token::CloseC));
     })
 }
 
+fn match_arm() -> impl Parser<Token> + Clone {
+    node(|builder| {
+        builder.name(Nodes::MatchArm);
+        builder.parse(pattern());
+        builder.parse(token(Token::FatArrow));
+        builder.parse(value());
+    })
+}
+
+fn pattern() -> impl Parser<Token> {
+    const PATTERN_TOKENS: &[Token] = &[
+        Token::Underscore,
+        Token::Number,
+        Token::True,
+        Token::False,
+        Token::DoubleQuote,
+        Token::Identifier,
+    ];
+
+    node(|builder| {
+        builder.name(Nodes::Pattern);
+        match builder.peek_token() {
+            Some(Token::Underscore) => builder.parse(wildcard()),
+            Some(Token::Number)
+            | Some(Token::True)
+            | Some(Token::False)
+            | Some(Token::DoubleQuote) => builder.parse(pattern_literal()),
+            Some(Token::Identifier) => {
+                builder.parse(named(identifier(), Nodes::PatternPath));
+                while let Some(Token::OpDot) = builder.peek_token() {
+                    builder.parse(token(Token::OpDot));
+                    builder.parse(named(identifier(), Nodes::PatternPath));
+                }
+                if let Some(Token::OpenP) = builder.peek_token() {
+                    builder.parse(token(Token::OpenP));
+                    builder.parse(separated(
+                        pattern_binding(),
+                        Token::Comma,
+                        Token::CloseP,
+                        true,
+                    ));
+                    builder.parse(token(Token::CloseP));
+                }
+            }
+            _ => builder.parse(expected(PATTERN_TOKENS)),
+        }
+    })
+}
+
+fn pattern_literal() -> impl Parser<Token> {
+    node(|builder| {
+        builder.name(Nodes::Virtual);
+        builder.name(Nodes::Value);
+        builder.name(Nodes::PatternLiteral);
+        match builder.peek_token() {
+            Some(Token::Number) => builder.parse(number()),
+            Some(Token::DoubleQuote) => builder.parse(string()),
+            _ => builder.parse(boolean()),
+        }
+    })
+}
+
+fn pattern_binding() -> impl Parser<Token> + Clone {
+    node(|builder| {
+        builder.name(Nodes::Virtual);
+        match builder.peek_token() {
+            Some(Token::Underscore) => builder.parse(wildcard()),
+            _ => builder.parse(named(identifier(), Nodes::PatternBinding)),
+        }
+    })
+}
+
+fn let_expr() -> impl Parser<Token> {
+    node(|builder| {
+        builder.name(Nodes::Let);
+        builder.parse(token(Token::Let));
+        builder.parse(let_pattern());
+        builder.parse(token(Token::OpAssign));
+        builder.parse(value());
+        builder.parse(token(Token::In));
+        builder.parse(value());
+    })
+}
+
+fn let_pattern() -> impl Parser<Token> + Clone {
+    node(|builder| {
+        builder.name(Nodes::Virtual);
+        match builder.peek_token() {
+            Some(Token::Underscore) => builder.parse(wildcard()),
+            Some(Token::OpenP) => builder.parse(node(|builder| {
+                builder.name(Nodes::PatternTuple);
+                builder.parse(token(Token::OpenP));
+                builder.parse(separated(let_pattern(), Token::Comma, Token::CloseP, true));
+                builder.parse(token(Token::CloseP));
+            })),
+            _ => builder.parse(named(identifier(), Nodes::PatternBinding)),
+        }
+    })
+}
+
+fn wildcard() -> impl Parser<Token> {
+    named(token(Token::Underscore), Nodes::Wildcard)
+}
+
 fn md_string() -> impl Parser<Token> {
     node(|builder| {
         builder.name(Nodes::Markdown);
@@ -182,14 +392,22 @@ fn inner_string() -> impl Parser<StrToken> {
 fn unary() -> impl Parser<Token> {
     node(|builder| {
         builder.name(Nodes::Unary);
+        let op = builder.peek_token();
         builder.parse(named(
             tokens(vec![Token::OpMinus, Token::OpBang, Token::OpDot]),
             Nodes::Op,
         ));
-        builder.parse(value());
+        match op {
+            Some(Token::OpDot) => builder.parse(named(identifier(), Nodes::Value)),
+            _ => builder.parse(value()),
+        }
     })
 }
 
+fn null() -> impl Parser<Token> {
+    named(token(Token::Null), Nodes::Null)
+}
+
 fn boolean() -> impl Parser<Token> {
     named(tokens(vec![Token::True, Token::False]), Nodes::Boolean)
 }
@@ -198,6 +416,10 @@ fn number() -> impl Parser<Token> {
     named(token(Token::Number), Nodes::Number)
 }
 
+fn date() -> impl Parser<Token> {
+    named(token(Token::Date), Nodes::Date)
+}
+
 pub(crate) fn trailing_trivia() -> impl Parser<Token> {
     node(|builder| {
         builder.name(Nodes::Trivia);
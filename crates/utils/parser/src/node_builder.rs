@@ -28,8 +28,12 @@ impl<'a, Tok: TokenKind> NodeBuilder<'a, Tok> {
     }
 
     pub fn peek_token(&mut self) -> Option<Tok> {
-        let saved = self.state.lexer().input().clone();
         let ctx = self.ctx;
+        self.peek_token_with(ctx)
+    }
+
+    pub fn peek_token_with<'b>(&mut self, ctx: &'b Context<'b, Tok>) -> Option<Tok> {
+        let saved = self.state.lexer().input().clone();
         if let Some(trivia) = ctx.leading_trivia() {
             let trivia_ctx = Context::default();
             trivia.parse(self.state, &trivia_ctx);